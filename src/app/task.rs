@@ -1,10 +1,11 @@
 // Copyright 2022 Camilo Suárez Sandí
 
-use std::io::Error;
-use std::io::ErrorKind;
+use std::fmt;
 
 use serde::Serialize;
 
+use crate::http::http_error::HttpError;
+
 #[derive(Debug, Serialize)]
 pub struct Task {
     index: usize,
@@ -45,13 +46,12 @@ impl Task {
         self.done
     }
 
-    pub fn from_string(string: &str) -> Result<Self, Error> {
+    /// Returns a task from a line of the data file
+    /// A bad line means the file is damaged, which is an error of the server
+    pub fn from_string(string: &str) -> Result<Self, HttpError> {
         if let Some((text, done)) = string.split_once('\t') {
             if done != "false" && done != "true" {
-                return Err(Error::new(
-                    ErrorKind::InvalidData,
-                    "Invalid task property: done",
-                ));
+                return Err(HttpError::internal("Invalid task property: done"));
             }
 
             let done = match done {
@@ -69,10 +69,12 @@ impl Task {
             return Ok(task);
         }
 
-        Err(Error::new(ErrorKind::InvalidData, "Invalid task"))
+        Err(HttpError::internal("Invalid task"))
     }
+}
 
-    pub fn to_string(&self) -> String {
-        format!("{}\t{}", self.text, self.done)
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\t{}", self.text, self.done)
     }
 }
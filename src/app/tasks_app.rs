@@ -1,104 +1,122 @@
 // Copyright 2022 Camilo Suárez Sandí
 
 use std::fs;
-use std::io::Error;
-use std::io::ErrorKind;
 use std::path;
+use std::sync::PoisonError;
+use std::sync::RwLock;
+use std::sync::RwLockReadGuard;
+use std::sync::RwLockWriteGuard;
 
 use crate::app::task::Task;
 use crate::http::http_app::HttpApp;
+use crate::http::http_config::HttpConfig;
+use crate::http::http_error::HttpError;
+use crate::http::http_method::HttpMethod;
+use crate::http::http_mime::MimeTypes;
+use crate::http::http_query::HttpQuery;
 use crate::http::http_request::HttpRequest;
 use crate::http::http_response::HttpResponse;
-
-use regex::Regex;
+use crate::http::http_response::RedirectKind;
+use crate::http::http_router::HttpRouter;
+use crate::http::http_router::RouteParams;
 
 pub struct TasksApp {
-    tasks: Vec<Task>,
+    /// The tasks, pages only read them so they can be served in parallel
+    tasks: RwLock<Vec<Task>>,
+    router: HttpRouter<TasksApp>,
+    /// The media types of the server settings
+    mime_types: MimeTypes,
 }
 
 impl HttpApp for TasksApp {
     fn handle(
-        &mut self,
+        &self,
         http_request: &HttpRequest,
         http_response: &mut HttpResponse,
-    ) -> Result<bool, Error> {
-        let target = http_request.get_target();
+    ) -> Result<bool, HttpError> {
+        self.router
+            .find(http_request)
+            .dispatch(self, http_request, http_response)
+    }
 
-        if http_request.get_method().get_verb() == "GET" && target == "/" {
-            self.read_data()?;
-            self.serve_index(http_response)?;
+    fn get_allowed_methods(&self, path: &str) -> Vec<HttpMethod> {
+        self.router.get_allowed_methods(path)
+    }
 
-            return Ok(true);
-        }
+    fn configure(&mut self, config: &HttpConfig) {
+        self.mime_types = config.get_mime_types().clone();
+    }
 
-        if http_request.get_method().get_verb() == "GET" && target.starts_with("/add?") {
-            self.serve_add(target)?;
-            self.redirect(http_response, "/");
-            self.write_data()?;
+    fn get_mime_types(&self) -> MimeTypes {
+        self.mime_types.clone()
+    }
+}
 
-            return Ok(true);
-        }
+impl TasksApp {
+    pub fn new() -> Self {
+        let mut router = HttpRouter::new();
 
-        if http_request.get_method().get_verb() == "GET" && target.starts_with("/update?") {
-            self.serve_update(target)?;
-            self.redirect(http_response, "/");
-            self.write_data()?;
+        router.get("/", Self::route_index);
+        router.get("/add", Self::route_add);
+        router.get("/update", Self::route_update);
 
-            return Ok(true);
+        Self {
+            tasks: RwLock::new(vec![]),
+            router,
+            mime_types: MimeTypes::new(),
         }
-
-        Ok(false)
     }
-}
 
-impl TasksApp {
-    pub fn new() -> Self {
-        Self { tasks: vec![] }
+    pub fn get_tasks(&self) -> RwLockReadGuard<'_, Vec<Task>> {
+        self.read_tasks()
     }
 
-    pub fn get_tasks(&self) -> &Vec<Task> {
-        &self.tasks
+    /// Locks the tasks to read them
+    /// A request that panicked does not lock out the rest, the saved tasks are still valid
+    fn read_tasks(&self) -> RwLockReadGuard<'_, Vec<Task>> {
+        self.tasks.read().unwrap_or_else(PoisonError::into_inner)
     }
 
-    pub fn read_data(&mut self) -> Result<(), Error> {
-        self.tasks.clear();
+    /// Locks the tasks to change them
+    fn write_tasks(&self) -> RwLockWriteGuard<'_, Vec<Task>> {
+        self.tasks.write().unwrap_or_else(PoisonError::into_inner)
+    }
 
+    /// Replaces the tasks with the ones in the data file
+    pub fn read_data(&self) -> Result<(), HttpError> {
         let file = path::Path::new("./data/tasks.txt");
 
         let data = fs::read_to_string(file)?;
 
-        let mut lines = data.lines();
+        let mut tasks = vec![];
 
-        let mut index = 1;
-
-        while let Some(line) = lines.next() {
+        for line in data.lines() {
             if !line.is_empty() {
-                let mut task = Task::from_string(line)?;
-
-                task.set_index(index);
-
-                self.tasks.push(task);
+                tasks.push(Task::from_string(line)?);
             }
-
-            index += 1;
         }
 
+        Self::set_indexes(&mut tasks);
+
+        *self.write_tasks() = tasks;
+
         Ok(())
     }
 
-    pub fn write_data(&self) -> Result<(), Error> {
-        let mut data = "".to_string();
+    /// Saves the tasks in the data file
+    pub fn write_data(&self) -> Result<(), HttpError> {
+        Self::save(&self.read_tasks())
+    }
 
-        let mut index = 0;
+    fn save(tasks: &[Task]) -> Result<(), HttpError> {
+        let mut data = "".to_string();
 
-        for task in &self.tasks {
+        for (index, task) in tasks.iter().enumerate() {
             data.push_str(&task.to_string());
 
-            if index < self.tasks.len() - 1 {
+            if index < tasks.len() - 1 {
                 data.push('\n');
             }
-
-            index += 1;
         }
 
         let file = path::Path::new("./data/tasks.txt");
@@ -108,117 +126,178 @@ impl TasksApp {
         Ok(())
     }
 
-    fn serve_index(&mut self, http_response: &mut HttpResponse) -> Result<(), Error> {
+    /// Numbers the tasks from 1, in the order they are shown
+    fn set_indexes(tasks: &mut [Task]) {
+        for (index, task) in tasks.iter_mut().enumerate() {
+            task.set_index(index + 1);
+        }
+    }
+
+    fn route_index(
+        &self,
+        _http_request: &HttpRequest,
+        _params: &RouteParams,
+        http_response: &mut HttpResponse,
+    ) -> Result<(), HttpError> {
+        self.serve_index(http_response)
+    }
+
+    fn route_add(
+        &self,
+        http_request: &HttpRequest,
+        _params: &RouteParams,
+        http_response: &mut HttpResponse,
+    ) -> Result<(), HttpError> {
+        // The lock is kept until the file is written, so it always matches the memory
+        let mut tasks = self.write_tasks();
+
+        Self::serve_add(&mut tasks, &http_request.get_query())?;
+        self.redirect(http_response, "/", RedirectKind::SeeOther);
+        Self::save(&tasks)
+    }
+
+    fn route_update(
+        &self,
+        http_request: &HttpRequest,
+        _params: &RouteParams,
+        http_response: &mut HttpResponse,
+    ) -> Result<(), HttpError> {
+        let mut tasks = self.write_tasks();
+
+        Self::serve_update(&mut tasks, &http_request.get_query())?;
+        self.redirect(http_response, "/", RedirectKind::SeeOther);
+        Self::save(&tasks)
+    }
+
+    fn serve_index(&self, http_response: &mut HttpResponse) -> Result<(), HttpError> {
         let file = path::Path::new("./pages/index.html");
 
         let data = fs::read_to_string(file)?;
 
         let mut context = tera::Context::new();
 
-        context.insert("tasks", &self.tasks);
-
-        let mut tera = tera::Tera::default();
+        context.insert("tasks", &*self.read_tasks());
 
-        let data = tera.render_str(&data, &context);
+        // The text of the tasks comes from the clients, so it is escaped
+        let data = tera::Tera::one_off(&data, &context, true).map_err(HttpError::internal)?;
 
-        if let Ok(data) = data {
-            http_response.add_body(&data);
-        }
+        http_response.add_header("Content-Type", "text/html; charset=utf-8");
+        http_response.add_body(&data);
 
         Ok(())
     }
 
-    fn serve_add(&mut self, target: &String) -> Result<(), Error> {
-        let target = target.strip_prefix("/add?").unwrap();
-
-        let re = Regex::new(r"text=([\p{L}\p{M}\p{Z}\p{S}\p{N}\p{P}]+)").unwrap();
+    fn serve_add(tasks: &mut Vec<Task>, query: &HttpQuery) -> Result<(), HttpError> {
+        let text = match query.get("text") {
+            Some(text) if !text.trim().is_empty() => text,
+            _ => return Err(HttpError::bad_request("The text of the task is missing")),
+        };
 
-        if !re.is_match(target) {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "Invalid url to add a task",
-            ));
-        }
+        // Tabs and line breaks are separators in the data file
+        let text = text.replace(['\t', '\r', '\n'], " ");
 
-        let text = re.captures(target).unwrap().get(1).unwrap().as_str();
+        tasks.push(Task::new(&text, false));
 
-        self.tasks.push(Task::new(text, false));
+        Self::set_indexes(tasks);
 
         Ok(())
     }
 
-    fn serve_update(&mut self, target: &String) -> Result<(), Error> {
-        let target = target.strip_prefix("/update?").unwrap();
+    fn serve_update(tasks: &mut Vec<Task>, query: &HttpQuery) -> Result<(), HttpError> {
+        if let Some(value) = query.get("check") {
+            let index = Self::parse_index(tasks, value)?;
 
-        if target.starts_with("check") {
-            let re = Regex::new(r"check=([1-9][0-9]*)").unwrap();
+            tasks[index].check();
 
-            if !re.is_match(target) {
-                return Err(Error::new(
-                    ErrorKind::InvalidInput,
-                    "Invalid url to check a task",
-                ));
-            }
+            return Ok(());
+        }
 
-            let text = re.captures(target).unwrap().get(1).unwrap().as_str();
+        if let Some(value) = query.get("uncheck") {
+            let index = Self::parse_index(tasks, value)?;
 
-            let index: usize = text.parse::<usize>().unwrap() - 1;
+            tasks[index].uncheck();
 
-            if index >= self.tasks.len() {
-                return Err(Error::new(ErrorKind::InvalidInput, "Invalid task index"));
-            }
+            return Ok(());
+        }
+
+        if let Some(value) = query.get("remove") {
+            let index = Self::parse_index(tasks, value)?;
 
-            self.tasks[index].check();
+            tasks.remove(index);
+
+            Self::set_indexes(tasks);
 
             return Ok(());
         }
 
-        if target.starts_with("uncheck") {
-            let re = Regex::new(r"uncheck=([1-9][0-9]*)").unwrap();
+        Ok(())
+    }
 
-            if !re.is_match(target) {
-                return Err(Error::new(
-                    ErrorKind::InvalidInput,
-                    "Invalid url to uncheck a task",
-                ));
-            }
+    /// Returns the position in the tasks of an index sent by the client, which starts at 1
+    fn parse_index(tasks: &[Task], value: &str) -> Result<usize, HttpError> {
+        let index = match value.parse::<usize>() {
+            Ok(index) if index >= 1 => index - 1,
+            _ => return Err(HttpError::bad_request("Invalid task index")),
+        };
 
-            let text = re.captures(target).unwrap().get(1).unwrap().as_str();
+        if index >= tasks.len() {
+            return Err(HttpError::not_found("The task does not exist"));
+        }
 
-            let index: usize = text.parse::<usize>().unwrap() - 1;
+        Ok(index)
+    }
+}
 
-            if index >= self.tasks.len() {
-                return Err(Error::new(ErrorKind::InvalidInput, "Invalid task index"));
-            }
+impl Default for TasksApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-            self.tasks[index].uncheck();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            return Ok(());
-        }
+    #[test]
+    fn index_escapes_the_text_of_the_tasks() {
+        let tasks_app = TasksApp::new();
 
-        if target.starts_with("remove") {
-            let re = Regex::new(r"remove=([1-9][0-9]*)").unwrap();
+        tasks_app
+            .write_tasks()
+            .push(Task::new("<script>alert(1)</script>", false));
+        tasks_app.write_tasks().push(Task::new("a & \"b\"", true));
 
-            if !re.is_match(target) {
-                return Err(Error::new(
-                    ErrorKind::InvalidInput,
-                    "Invalid url to uncheck a task",
-                ));
-            }
+        let mut http_response = HttpResponse::new();
 
-            let text = re.captures(target).unwrap().get(1).unwrap().as_str();
+        tasks_app.serve_index(&mut http_response).ok().unwrap();
 
-            let index: usize = text.parse::<usize>().unwrap() - 1;
+        let body = String::from_utf8_lossy(http_response.get_body());
 
-            if index >= self.tasks.len() {
-                return Err(Error::new(ErrorKind::InvalidInput, "Invalid task index"));
-            }
+        assert!(!body.contains("<script>"));
+        assert!(body.contains("&lt;script&gt;alert(1)&lt;&#x2F;script&gt;"));
+        assert!(body.contains("a &amp; &quot;b&quot;"));
+    }
 
-            self.tasks.remove(index);
+    #[test]
+    fn static_files_use_the_media_types_of_the_settings() {
+        let mut config = HttpConfig::new();
 
-            return Ok(());
-        }
+        config.set_mime_type("css", "application/x-stylesheet");
 
-        Ok(())
+        let mut tasks_app = TasksApp::new();
+
+        tasks_app.configure(&config);
+
+        let mut http_response = HttpResponse::new();
+
+        tasks_app
+            .serve_static(&mut http_response, "./public/main.css")
+            .ok()
+            .unwrap();
+
+        assert_eq!(
+            http_response.get_header("Content-Type"),
+            Some("application/x-stylesheet")
+        );
     }
 }
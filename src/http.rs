@@ -1,7 +1,18 @@
 // Copyright 2022 Camilo Suárez Sandí
 
 pub mod http_app;
+pub mod http_compression;
+pub mod http_config;
+pub mod http_deadline;
+pub mod http_error;
+pub mod http_headers;
 pub mod http_method;
+pub mod http_mime;
+pub mod http_query;
+pub mod http_range;
 pub mod http_request;
 pub mod http_response;
+pub mod http_router;
 pub mod http_server;
+pub mod http_static;
+pub mod http_status;
@@ -1,32 +1,101 @@
 // Copyright 2022 Camilo Suárez Sandí
 
 use std::fs;
-use std::io::Error;
 use std::path;
 
+use crate::http::http_config::HttpConfig;
+use crate::http::http_error::HttpError;
+use crate::http::http_method::HttpMethod;
+use crate::http::http_mime::MimeTypes;
 use crate::http::http_request::HttpRequest;
 use crate::http::http_response::HttpResponse;
+use crate::http::http_response::RedirectKind;
 
-pub trait HttpApp {
+/// An app that answers some of the requests of the server
+/// Requests are handled in parallel, so an app keeps its own state behind locks
+pub trait HttpApp: Send + Sync {
+    /// Returns false if the request is not for this app
+    /// An error is answered with its code, and its message is shown to the client
     fn handle(
-        &mut self,
+        &self,
         http_request: &HttpRequest,
         http_response: &mut HttpResponse,
-    ) -> Result<bool, Error>;
+    ) -> Result<bool, HttpError>;
 
-    fn serve_static(&self, http_response: &mut HttpResponse, path: &str) -> Result<(), Error> {
+    /// Returns the methods the app answers for a path, or for any path if it is *
+    /// They are used to answer OPTIONS
+    fn get_allowed_methods(&self, _path: &str) -> Vec<HttpMethod> {
+        Vec::new()
+    }
+
+    /// Fills the response of an error, its code and message are already set
+    /// Returns false to let the server serve its own page
+    fn serve_error(
+        &self,
+        _http_error: &HttpError,
+        _http_response: &mut HttpResponse,
+    ) -> Result<bool, HttpError> {
+        Ok(false)
+    }
+
+    /// Called once when the server starts, with its settings
+    /// An app keeps the ones it needs, like the media types for serve_static
+    fn configure(&mut self, _config: &HttpConfig) {}
+
+    /// Returns the media types used by serve_static
+    /// An app that keeps the ones of the settings in configure gets their overrides
+    fn get_mime_types(&self) -> MimeTypes {
+        MimeTypes::new()
+    }
+
+    /// Serves a file with the media type of its extension
+    fn serve_static(&self, http_response: &mut HttpResponse, path: &str) -> Result<(), HttpError> {
         let file = path::Path::new(path);
 
-        let data = fs::read_to_string(file)?;
+        let data = fs::read(file)?;
 
-        http_response.add_body(&data);
+        http_response.add_header("Content-Type", &self.get_mime_types().content_type(file));
+        http_response.add_body_bytes(&data);
 
         Ok(())
     }
 
-    fn redirect(&self, http_response: &mut HttpResponse, target: &str) {
-        http_response.set_code(302);
-        http_response.set_message("Found");
+    /// Redirects the client to a target
+    /// The headers set before, like Set-Cookie, are kept
+    fn redirect(&self, http_response: &mut HttpResponse, target: &str, kind: RedirectKind) {
+        http_response.set_code(kind.get_code());
         http_response.add_header("Location", target);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::http_status::StatusCode;
+
+    /// An app that does not handle any request
+    struct EmptyApp;
+
+    impl HttpApp for EmptyApp {
+        fn handle(&self, _: &HttpRequest, _: &mut HttpResponse) -> Result<bool, HttpError> {
+            Ok(false)
+        }
+    }
+
+    #[test]
+    fn redirect_keeps_the_headers_set_before() {
+        let mut http_response = HttpResponse::new();
+
+        http_response.append_header("Set-Cookie", "a=1");
+        http_response.append_header("Set-Cookie", "b=2");
+
+        EmptyApp.redirect(&mut http_response, "/", RedirectKind::SeeOther);
+
+        assert_eq!(http_response.get_code(), StatusCode::SEE_OTHER);
+        assert_eq!(http_response.get_header("Location"), Some("/"));
+        assert_eq!(
+            http_response.get_headers().get_all("Set-Cookie"),
+            vec!["a=1", "b=2"]
+        );
+    }
+}
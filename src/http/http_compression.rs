@@ -0,0 +1,161 @@
+// Copyright 2023 Camilo Suárez Sandí
+
+use std::io::Error;
+use std::io::Write;
+
+use flate2::write::DeflateEncoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::http::http_config::HttpConfig;
+use crate::http::http_request::HttpRequest;
+use crate::http::http_response::HttpResponse;
+
+/// The types that are compressed, besides text/*
+static COMPRESSIBLE_TYPES: [&str; 6] = [
+    "application/javascript",
+    "application/json",
+    "application/manifest+json",
+    "application/wasm",
+    "application/xml",
+    "image/svg+xml",
+];
+
+/// A content coding the server can compress with
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ContentCoding {
+    Gzip,
+    Deflate,
+}
+
+impl ContentCoding {
+    /// Returns the name of the coding, as used in Content-Encoding
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ContentCoding::Gzip => "gzip",
+            ContentCoding::Deflate => "deflate",
+        }
+    }
+
+    /// Returns the coding the client prefers from an Accept-Encoding value, if it accepts one
+    /// The qualities are respected, and gzip wins a tie
+    pub fn negotiate(accept_encoding: &str) -> Option<Self> {
+        let mut best: Option<(Self, f32)> = None;
+
+        for coding in [ContentCoding::Gzip, ContentCoding::Deflate] {
+            let quality = Self::get_quality(accept_encoding, coding.get_name());
+
+            if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+                best = Some((coding, quality));
+            }
+        }
+
+        best.map(|(coding, _)| coding)
+    }
+
+    /// Returns true if an Accept-Encoding value accepts the coding
+    pub fn is_accepted(&self, accept_encoding: &str) -> bool {
+        Self::get_quality(accept_encoding, self.get_name()) > 0.0
+    }
+
+    /// Returns the quality of a coding in an Accept-Encoding value, 0 if it is not accepted
+    /// A coding that is not listed takes the quality of *
+    fn get_quality(accept_encoding: &str, name: &str) -> f32 {
+        let mut wildcard = 0.0;
+
+        for item in accept_encoding.split(',') {
+            let mut parts = item.split(';').map(str::trim);
+
+            let coding = parts.next().unwrap_or("");
+
+            let quality = parts
+                .find_map(|param| param.strip_prefix("q=").or(param.strip_prefix("Q=")))
+                .map_or(1.0, |quality| quality.parse().unwrap_or(0.0));
+
+            if coding.eq_ignore_ascii_case(name) {
+                return quality;
+            }
+
+            if coding == "*" {
+                wildcard = quality;
+            }
+        }
+
+        wildcard
+    }
+
+    /// Returns the compressed bytes
+    pub fn encode(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            ContentCoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+            ContentCoding::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Compresses the body of a response, if the settings allow it and the client accepts it
+/// Only bodies kept in memory, of a compressible type and above the threshold are compressed
+pub fn compress_response(
+    http_request: &HttpRequest,
+    http_response: &mut HttpResponse,
+    config: &HttpConfig,
+) -> Result<(), Error> {
+    if !config.get_compression()
+        || http_response.has_body_reader()
+        || http_response.get_header("Content-Encoding").is_some()
+        || !is_compressible(http_response.get_header("Content-Type"))
+    {
+        return Ok(());
+    }
+
+    // The body depends on Accept-Encoding, even when it is not compressed
+    add_vary(http_response, "Accept-Encoding");
+
+    if http_response.get_body().len() < config.get_compression_threshold() {
+        return Ok(());
+    }
+
+    let coding = match http_request
+        .get_header("Accept-Encoding")
+        .and_then(ContentCoding::negotiate)
+    {
+        Some(coding) => coding,
+        None => return Ok(()),
+    };
+
+    let body = coding.encode(http_response.get_body())?;
+
+    http_response.set_body_bytes(body);
+    http_response.add_header("Content-Encoding", coding.get_name());
+
+    Ok(())
+}
+
+/// Returns true if a Content-Type is worth compressing, like text or json
+pub fn is_compressible(content_type: Option<&str>) -> bool {
+    let mime_type = match content_type {
+        Some(content_type) => content_type.split(';').next().unwrap_or("").trim(),
+        None => return false,
+    };
+
+    mime_type.starts_with("text/") || COMPRESSIBLE_TYPES.contains(&mime_type)
+}
+
+/// Adds a header name to the Vary header of a response, if it is not there yet
+pub fn add_vary(http_response: &mut HttpResponse, name: &str) {
+    if http_response.get_headers().has_token("Vary", name) {
+        return;
+    }
+
+    http_response.append_header("Vary", name);
+}
@@ -0,0 +1,346 @@
+// Copyright 2023 Camilo Suárez Sandí
+
+use std::time::Duration;
+
+use crate::http::http_mime::MimeTypes;
+
+/// The default maximum size of a request body, in bytes
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// The default maximum length of the request line, in bytes
+pub const DEFAULT_MAX_REQUEST_LINE_LENGTH: usize = 8 * 1024;
+
+/// The default maximum size of all the request headers, in bytes
+pub const DEFAULT_MAX_HEADER_SIZE: usize = 16 * 1024;
+
+/// The default maximum number of request headers
+pub const DEFAULT_MAX_HEADER_COUNT: usize = 100;
+
+/// The default time a single read of a connection can wait
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The default time a single write of a connection can wait
+pub const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The default time a client has to send the request line and the headers
+pub const DEFAULT_HEADER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The default time a persistent connection can stay idle
+pub const DEFAULT_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The default maximum number of requests served on one connection
+pub const DEFAULT_MAX_REQUESTS_PER_CONNECTION: usize = 100;
+
+/// The default time a shutdown waits for the connections in progress
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The default maximum number of connections waiting for a worker
+pub const DEFAULT_QUEUE_CAPACITY: usize = 256;
+
+/// The default directory of the static files
+pub const DEFAULT_PUBLIC_ROOT: &str = "./public";
+
+/// The default minimum size of a body to compress it, in bytes
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+
+/// What the server does with a new connection when all the workers are busy and the queue is full
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OverflowPolicy {
+    /// Stop accepting connections until the queue has room
+    Block,
+    /// Answer 503 Service Unavailable and close the connection
+    Reject,
+    /// Close the connection without an answer
+    Drop,
+}
+
+/// The settings of an http server
+#[derive(Clone)]
+pub struct HttpConfig {
+    /// The maximum size of a request body, in bytes
+    max_body_size: usize,
+    /// The maximum length of the request line, in bytes
+    max_request_line_length: usize,
+    /// The maximum size of all the request headers, in bytes
+    max_header_size: usize,
+    /// The maximum number of request headers
+    max_header_count: usize,
+    /// The time a single read of a request can wait
+    read_timeout: Duration,
+    /// The time a single write of a response can wait
+    write_timeout: Duration,
+    /// The time a client has to send the request line and the headers, from its first byte
+    header_timeout: Duration,
+    /// The time a persistent connection can wait for its next request
+    keep_alive_timeout: Duration,
+    /// The maximum number of requests served on one connection
+    max_requests_per_connection: usize,
+    /// The time a shutdown waits for the connections in progress
+    shutdown_timeout: Duration,
+    /// The number of workers, by default the available parallelism
+    workers_count: Option<usize>,
+    /// The maximum number of connections waiting for a worker
+    queue_capacity: usize,
+    /// What to do with connections when the queue is full
+    overflow_policy: OverflowPolicy,
+    /// The directory of the static files
+    public_root: String,
+    /// The media types of the static files, by extension
+    mime_types: MimeTypes,
+    /// The Cache-Control values of the static files, by path prefix
+    cache_controls: Vec<(String, String)>,
+    /// If the bodies are compressed for the clients that accept it
+    compression: bool,
+    /// The minimum size of a body to compress it, in bytes
+    compression_threshold: usize,
+    /// If the .gz siblings of the static files are served to the clients that accept gzip
+    precompressed: bool,
+    /// If the directories of the static files without an index.html are listed
+    directory_listing: bool,
+}
+
+impl HttpConfig {
+    /// HttpConfig constructor
+    /// Returns the default settings
+    pub fn new() -> Self {
+        Self {
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            max_request_line_length: DEFAULT_MAX_REQUEST_LINE_LENGTH,
+            max_header_size: DEFAULT_MAX_HEADER_SIZE,
+            max_header_count: DEFAULT_MAX_HEADER_COUNT,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            write_timeout: DEFAULT_WRITE_TIMEOUT,
+            header_timeout: DEFAULT_HEADER_TIMEOUT,
+            keep_alive_timeout: DEFAULT_KEEP_ALIVE_TIMEOUT,
+            max_requests_per_connection: DEFAULT_MAX_REQUESTS_PER_CONNECTION,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            workers_count: None,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            overflow_policy: OverflowPolicy::Block,
+            public_root: DEFAULT_PUBLIC_ROOT.to_string(),
+            mime_types: MimeTypes::new(),
+            cache_controls: Vec::new(),
+            compression: false,
+            compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            precompressed: false,
+            directory_listing: false,
+        }
+    }
+
+    /// Getter for the maximum body size
+    pub fn get_max_body_size(&self) -> usize {
+        self.max_body_size
+    }
+
+    /// Setter for the maximum body size
+    pub fn set_max_body_size(&mut self, max_body_size: usize) {
+        self.max_body_size = max_body_size;
+    }
+
+    /// Getter for the maximum request line length
+    pub fn get_max_request_line_length(&self) -> usize {
+        self.max_request_line_length
+    }
+
+    /// Setter for the maximum request line length
+    /// Longer request lines are answered with 414 URI Too Long
+    pub fn set_max_request_line_length(&mut self, max_request_line_length: usize) {
+        self.max_request_line_length = max_request_line_length;
+    }
+
+    /// Getter for the maximum header size
+    pub fn get_max_header_size(&self) -> usize {
+        self.max_header_size
+    }
+
+    /// Setter for the maximum header size
+    /// Larger headers are answered with 431 Request Header Fields Too Large
+    pub fn set_max_header_size(&mut self, max_header_size: usize) {
+        self.max_header_size = max_header_size;
+    }
+
+    /// Getter for the maximum header count
+    pub fn get_max_header_count(&self) -> usize {
+        self.max_header_count
+    }
+
+    /// Setter for the maximum header count
+    /// More headers are answered with 431 Request Header Fields Too Large
+    pub fn set_max_header_count(&mut self, max_header_count: usize) {
+        self.max_header_count = max_header_count;
+    }
+
+    /// Getter for the read timeout
+    pub fn get_read_timeout(&self) -> Duration {
+        self.read_timeout
+    }
+
+    /// Setter for the read timeout
+    pub fn set_read_timeout(&mut self, read_timeout: Duration) {
+        self.read_timeout = read_timeout;
+    }
+
+    /// Getter for the write timeout
+    pub fn get_write_timeout(&self) -> Duration {
+        self.write_timeout
+    }
+
+    /// Setter for the write timeout
+    pub fn set_write_timeout(&mut self, write_timeout: Duration) {
+        self.write_timeout = write_timeout;
+    }
+
+    /// Getter for the header timeout
+    pub fn get_header_timeout(&self) -> Duration {
+        self.header_timeout
+    }
+
+    /// Setter for the header timeout
+    /// A client that is still sending its headers after it is answered with 408 Request Timeout
+    pub fn set_header_timeout(&mut self, header_timeout: Duration) {
+        self.header_timeout = header_timeout;
+    }
+
+    /// Getter for the keep alive timeout
+    pub fn get_keep_alive_timeout(&self) -> Duration {
+        self.keep_alive_timeout
+    }
+
+    /// Setter for the keep alive timeout
+    pub fn set_keep_alive_timeout(&mut self, keep_alive_timeout: Duration) {
+        self.keep_alive_timeout = keep_alive_timeout;
+    }
+
+    /// Getter for the maximum number of requests per connection
+    pub fn get_max_requests_per_connection(&self) -> usize {
+        self.max_requests_per_connection
+    }
+
+    /// Setter for the maximum number of requests per connection
+    /// A value of 1 disables persistent connections
+    pub fn set_max_requests_per_connection(&mut self, max_requests_per_connection: usize) {
+        self.max_requests_per_connection = max_requests_per_connection.max(1);
+    }
+
+    /// Getter for the shutdown timeout
+    pub fn get_shutdown_timeout(&self) -> Duration {
+        self.shutdown_timeout
+    }
+
+    /// Setter for the shutdown timeout
+    pub fn set_shutdown_timeout(&mut self, shutdown_timeout: Duration) {
+        self.shutdown_timeout = shutdown_timeout;
+    }
+
+    /// Getter for the workers count, None means one per available core
+    pub fn get_workers_count(&self) -> Option<usize> {
+        self.workers_count
+    }
+
+    /// Setter for the workers count
+    pub fn set_workers_count(&mut self, workers_count: usize) {
+        self.workers_count = Some(workers_count.max(1));
+    }
+
+    /// Getter for the queue capacity
+    pub fn get_queue_capacity(&self) -> usize {
+        self.queue_capacity
+    }
+
+    /// Setter for the queue capacity
+    pub fn set_queue_capacity(&mut self, queue_capacity: usize) {
+        self.queue_capacity = queue_capacity;
+    }
+
+    /// Getter for the overflow policy
+    pub fn get_overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
+    /// Setter for the overflow policy
+    pub fn set_overflow_policy(&mut self, overflow_policy: OverflowPolicy) {
+        self.overflow_policy = overflow_policy;
+    }
+
+    /// Getter for the public root
+    pub fn get_public_root(&self) -> &String {
+        &self.public_root
+    }
+
+    /// Setter for the public root
+    pub fn set_public_root(&mut self, public_root: &str) {
+        self.public_root = public_root.to_string();
+    }
+
+    /// Getter for the media types
+    pub fn get_mime_types(&self) -> &MimeTypes {
+        &self.mime_types
+    }
+
+    /// Adds or replaces the media type of an extension, like ("log", "text/plain")
+    pub fn set_mime_type(&mut self, extension: &str, mime_type: &str) {
+        self.mime_types.set(extension, mime_type);
+    }
+
+    /// Getter for the Cache-Control values, by path prefix
+    pub fn get_cache_controls(&self) -> &[(String, String)] {
+        &self.cache_controls
+    }
+
+    /// Sets the Cache-Control of the static files under a path prefix, like ("/images/", "max-age=3600")
+    /// When several prefixes match a path, the longest one is used
+    pub fn set_cache_control(&mut self, prefix: &str, cache_control: &str) {
+        self.cache_controls.retain(|(current, _)| current != prefix);
+        self.cache_controls
+            .push((prefix.to_string(), cache_control.to_string()));
+    }
+
+    /// Getter for the compression
+    pub fn get_compression(&self) -> bool {
+        self.compression
+    }
+
+    /// Setter for the compression
+    /// Bodies of text, json and similar types are compressed with gzip or deflate
+    pub fn set_compression(&mut self, compression: bool) {
+        self.compression = compression;
+    }
+
+    /// Getter for the compression threshold
+    pub fn get_compression_threshold(&self) -> usize {
+        self.compression_threshold
+    }
+
+    /// Setter for the compression threshold
+    pub fn set_compression_threshold(&mut self, compression_threshold: usize) {
+        self.compression_threshold = compression_threshold;
+    }
+
+    /// Getter for the precompressed static files
+    pub fn get_precompressed(&self) -> bool {
+        self.precompressed
+    }
+
+    /// Setter for the precompressed static files
+    /// When enabled, main.css.gz is served for main.css if it exists
+    pub fn set_precompressed(&mut self, precompressed: bool) {
+        self.precompressed = precompressed;
+    }
+
+    /// Getter for the directory listing
+    pub fn get_directory_listing(&self) -> bool {
+        self.directory_listing
+    }
+
+    /// Setter for the directory listing
+    /// The directories without an index.html are answered with a list of their files
+    pub fn set_directory_listing(&mut self, directory_listing: bool) {
+        self.directory_listing = directory_listing;
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
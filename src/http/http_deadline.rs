@@ -0,0 +1,67 @@
+// Copyright 2023 Camilo Suárez Sandí
+
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::net::TcpStream;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The shortest timeout a socket accepts, zero means no timeout
+const MIN_TIMEOUT: Duration = Duration::from_millis(1);
+
+/// Reads from a TcpStream with a timeout for each read and an optional deadline for all of them
+/// A client that sends a byte now and then can not keep a worker past the deadline
+pub struct DeadlineReader<'a> {
+    /// The stream of the connection
+    stream: &'a TcpStream,
+    /// The longest time a single read can wait
+    timeout: Duration,
+    /// The time when all the reads have to be done
+    deadline: Option<Instant>,
+}
+
+impl<'a> DeadlineReader<'a> {
+    /// DeadlineReader constructor
+    /// Returns a reader without a deadline
+    pub fn new(stream: &'a TcpStream, timeout: Duration) -> Self {
+        Self {
+            stream,
+            timeout,
+            deadline: None,
+        }
+    }
+
+    /// Setter for the timeout of each read
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Setter for the deadline, None removes it
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        let mut timeout = self.timeout;
+
+        if let Some(deadline) = self.deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+
+            if remaining.is_zero() {
+                return Err(Error::new(ErrorKind::TimedOut, "The deadline expired"));
+            }
+
+            timeout = timeout.min(remaining);
+        }
+
+        self.stream
+            .set_read_timeout(Some(timeout.max(MIN_TIMEOUT)))?;
+
+        let mut stream = self.stream;
+
+        stream.read(buffer)
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright 2023 Camilo Suárez Sandí
+
+use std::error;
+use std::fmt;
+use std::io;
+
+use crate::http::http_status::StatusCode;
+
+/// The cause of an error, kept for the logs
+type ErrorSource = Box<dyn error::Error + Send + Sync>;
+
+/// An error that becomes an http response
+#[derive(Debug)]
+pub struct HttpError {
+    /// The status code of the response, like 400 or 500
+    code: StatusCode,
+    /// A message that is safe to show to the client
+    message: String,
+    /// The error that caused this one, it is never shown to the client
+    source: Option<ErrorSource>,
+}
+
+impl HttpError {
+    /// HttpError constructor
+    /// Returns an error from a status code and a message for the client
+    pub fn new(code: StatusCode, message: &str) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            source: None,
+        }
+    }
+
+    /// Returns a 400 Bad Request error
+    pub fn bad_request(message: &str) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    /// Returns a 404 Not Found error
+    pub fn not_found(message: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, message)
+    }
+
+    /// Returns a 500 Internal Server Error, the source is only logged
+    pub fn internal<E: Into<ErrorSource>>(source: E) -> Self {
+        let code = StatusCode::INTERNAL_SERVER_ERROR;
+
+        Self::new(code, code.get_reason_phrase()).with_source(source)
+    }
+
+    /// Sets the error that caused this one
+    pub fn with_source<E: Into<ErrorSource>>(mut self, source: E) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Getter for the code
+    pub fn get_code(&self) -> StatusCode {
+        self.code
+    }
+
+    /// Getter for the message
+    pub fn get_message(&self) -> &String {
+        &self.message
+    }
+
+    /// Returns the io error that caused this one, if there is one
+    pub fn get_io_error(&self) -> Option<&io::Error> {
+        self.source
+            .as_ref()
+            .and_then(|source| source.downcast_ref::<io::Error>())
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.code.get_code(), self.message)?;
+
+        if let Some(source) = &self.source {
+            write!(f, ": {}", source)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl error::Error for HttpError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| source.as_ref() as &(dyn error::Error + 'static))
+    }
+}
+
+/// Disk and network failures are the fault of the server
+impl From<io::Error> for HttpError {
+    fn from(error: io::Error) -> Self {
+        Self::internal(error)
+    }
+}
+
+impl From<HttpError> for io::Error {
+    fn from(error: HttpError) -> Self {
+        io::Error::other(error)
+    }
+}
@@ -0,0 +1,312 @@
+// Copyright 2023 Camilo Suárez Sandí
+
+use std::time::SystemTime;
+
+use crate::http::http_error::HttpError;
+
+/// The headers of a request or a response
+/// Names ignore their case, values are trimmed, and a name can have several values in order
+#[derive(Clone, Default, Debug)]
+pub struct HeaderMap {
+    /// The names, as they were added, and the values, in the order they were added
+    entries: Vec<(String, String)>,
+}
+
+impl HeaderMap {
+    /// HeaderMap constructor
+    /// Returns a map without headers
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Replaces all the values of a header with a single one
+    /// The header keeps the place of its first value, new headers go at the end
+    pub fn insert(&mut self, name: &str, value: &str) {
+        let value = value.trim().to_string();
+
+        match self.position(name) {
+            Some(position) => {
+                self.entries[position].1 = value;
+
+                let mut index = position + 1;
+
+                while index < self.entries.len() {
+                    if self.entries[index].0.eq_ignore_ascii_case(name) {
+                        self.entries.remove(index);
+                    } else {
+                        index += 1;
+                    }
+                }
+            }
+            None => self.entries.push((name.to_string(), value)),
+        }
+    }
+
+    /// Adds a value to a header, after the ones it already has
+    pub fn append(&mut self, name: &str, value: &str) {
+        self.entries
+            .push((name.to_string(), value.trim().to_string()));
+    }
+
+    /// Returns the first value of a header
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.position(name)
+            .map(|position| self.entries[position].1.as_str())
+    }
+
+    /// Returns all the values of a header, in order
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|(current, _)| current.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    /// Returns the items of a header that is a comma separated list, from all its values
+    /// Empty items are skipped
+    pub fn get_list(&self, name: &str) -> Vec<&str> {
+        self.get_all(name)
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+
+    /// Returns true if a comma separated header has a token, ignoring its case
+    /// Like close in Connection: keep-alive, close
+    pub fn has_token(&self, name: &str, token: &str) -> bool {
+        self.get_list(name)
+            .iter()
+            .any(|item| item.eq_ignore_ascii_case(token))
+    }
+
+    /// Returns true if there is a header with a name
+    pub fn contains(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    /// Removes all the values of a header
+    pub fn remove(&mut self, name: &str) {
+        self.entries
+            .retain(|(current, _)| !current.eq_ignore_ascii_case(name));
+    }
+
+    /// Returns the names and values, in order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Returns the number of values
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if there are no headers
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the Content-Length
+    /// Repeated values are allowed only if they are all the same
+    pub fn get_content_length(&self) -> Result<Option<u64>, HttpError> {
+        let mut content_length = None;
+
+        for value in self.get_list("Content-Length") {
+            if !value.bytes().all(|byte| byte.is_ascii_digit()) {
+                return Err(HttpError::bad_request("Invalid Content-Length header"));
+            }
+
+            let value: u64 = value
+                .parse()
+                .map_err(|_| HttpError::bad_request("Invalid Content-Length header"))?;
+
+            if content_length.is_some_and(|content_length| content_length != value) {
+                return Err(HttpError::bad_request("Invalid Content-Length header"));
+            }
+
+            content_length = Some(value);
+        }
+
+        Ok(content_length)
+    }
+
+    /// Returns the media type of the Content-Type, without its parameters, like text/html
+    pub fn get_content_type(&self) -> Option<&str> {
+        self.get("Content-Type")
+            .map(|value| value.split(';').next().unwrap_or_default().trim())
+    }
+
+    /// Returns the Host
+    pub fn get_host(&self) -> Option<&str> {
+        self.get("Host")
+    }
+
+    /// Returns the value of a header that is an http date, like If-Modified-Since
+    /// An invalid date is the same as a missing one
+    pub fn get_date(&self, name: &str) -> Option<SystemTime> {
+        self.get(name)
+            .and_then(|value| httpdate::parse_http_date(value).ok())
+    }
+
+    /// Returns the position of the first value of a header
+    fn position(&self, name: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|(current, _)| current.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Returns true if a string is a token, the characters allowed in header names and methods
+pub fn is_token(string: &str) -> bool {
+    !string.is_empty()
+        && string
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_ignores_the_case_of_the_name() {
+        let mut headers = HeaderMap::new();
+
+        headers.append("Content-Type", "text/html");
+
+        assert_eq!(headers.get("content-type"), Some("text/html"));
+        assert_eq!(headers.get("CONTENT-TYPE"), Some("text/html"));
+        assert!(headers.contains("Content-type"));
+        assert_eq!(headers.get("Content-Length"), None);
+    }
+
+    #[test]
+    fn values_are_trimmed() {
+        let mut headers = HeaderMap::new();
+
+        headers.append("Host", "  localhost \t");
+        headers.insert("Accept", " */* ");
+
+        assert_eq!(headers.get("Host"), Some("localhost"));
+        assert_eq!(headers.get("Accept"), Some("*/*"));
+    }
+
+    #[test]
+    fn append_keeps_every_value_in_order() {
+        let mut headers = HeaderMap::new();
+
+        headers.append("Vary", "Accept");
+        headers.append("Host", "localhost");
+        headers.append("vary", "Accept-Encoding");
+
+        assert_eq!(headers.get("Vary"), Some("Accept"));
+        assert_eq!(headers.get_all("Vary"), vec!["Accept", "Accept-Encoding"]);
+        assert_eq!(headers.len(), 3);
+    }
+
+    #[test]
+    fn insert_replaces_every_value_keeping_the_first_place() {
+        let mut headers = HeaderMap::new();
+
+        headers.append("Vary", "Accept");
+        headers.append("Host", "localhost");
+        headers.append("Vary", "Accept-Encoding");
+        headers.insert("VARY", "Origin");
+        headers.insert("Date", "today");
+
+        let entries: Vec<(&str, &str)> = headers.iter().collect();
+
+        assert_eq!(
+            entries,
+            vec![("Vary", "Origin"), ("Host", "localhost"), ("Date", "today")]
+        );
+    }
+
+    #[test]
+    fn remove_takes_every_value() {
+        let mut headers = HeaderMap::new();
+
+        headers.append("Vary", "Accept");
+        headers.append("vary", "Origin");
+        headers.remove("VARY");
+
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn get_list_joins_the_values_and_skips_empty_items() {
+        let mut headers = HeaderMap::new();
+
+        headers.append("Accept-Encoding", "gzip, ,deflate,");
+        headers.append("Accept-Encoding", "");
+        headers.append("Accept-Encoding", " br ");
+
+        assert_eq!(
+            headers.get_list("Accept-Encoding"),
+            vec!["gzip", "deflate", "br"]
+        );
+    }
+
+    #[test]
+    fn has_token_ignores_case_and_empty_items() {
+        let mut headers = HeaderMap::new();
+
+        headers.append("Connection", "keep-alive, , Close");
+
+        assert!(headers.has_token("Connection", "close"));
+        assert!(headers.has_token("connection", "KEEP-ALIVE"));
+        assert!(!headers.has_token("Connection", ""));
+        assert!(!headers.has_token("Connection", "upgrade"));
+    }
+
+    /// Returns the Content-Length of a map with some values of it
+    fn content_length(values: &[&str]) -> Result<Option<u64>, HttpError> {
+        let mut headers = HeaderMap::new();
+
+        for value in values {
+            headers.append("Content-Length", value);
+        }
+
+        headers.get_content_length()
+    }
+
+    #[test]
+    fn content_length_is_parsed() {
+        assert_eq!(content_length(&[]).ok(), Some(None));
+        assert_eq!(content_length(&["42"]).ok(), Some(Some(42)));
+    }
+
+    #[test]
+    fn content_length_repeated_with_the_same_value_is_allowed() {
+        assert_eq!(content_length(&["42", "42"]).ok(), Some(Some(42)));
+        assert_eq!(content_length(&["42, 42"]).ok(), Some(Some(42)));
+    }
+
+    #[test]
+    fn conflicting_content_lengths_are_rejected() {
+        assert!(content_length(&["42", "43"]).is_err());
+        assert!(content_length(&["42, 0"]).is_err());
+    }
+
+    #[test]
+    fn signed_or_invalid_content_lengths_are_rejected() {
+        for value in ["+42", "-1", "4 2", "0x10", "abc", "99999999999999999999999"] {
+            assert!(content_length(&[value]).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn tokens() {
+        assert!(is_token("Content-Type"));
+        assert!(is_token("X-Custom_Header.1~"));
+        assert!(!is_token(""));
+        assert!(!is_token("Content Type"));
+        assert!(!is_token("Name:"));
+    }
+}
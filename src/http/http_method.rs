@@ -1,37 +1,87 @@
 // Copyright 2022 Camilo Suárez Sandí
 
-use std::io::Error;
-use std::io::ErrorKind;
+use std::fmt;
 
-/// All the possible http methods
-static HTTP_METHODS: [&str; 9] = [
-    "GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH",
-];
+use crate::http::http_error::HttpError;
+use crate::http::http_headers::is_token;
 
 /// An http method
-pub struct HttpMethod {
-    /// The verb of the http method
-    verb: String,
+/// The ones the server does not know, like PROPFIND, are kept as they were sent
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum HttpMethod {
+    Get,
+    Head,
+    Post,
+    Put,
+    Delete,
+    Connect,
+    Options,
+    Trace,
+    Patch,
+    /// An extension method, like the ones of WebDAV
+    Other(String),
 }
 
 impl HttpMethod {
     /// HttpMethod constructor
-    /// Returns an http method from a verb
-    /// It requires that the verb is one of the possible http methods
-    pub fn new(verb: &str) -> Result<Self, Error> {
-        for http_method in HTTP_METHODS {
-            if verb == http_method {
-                return Ok(Self {
-                    verb: verb.to_string(),
-                });
-            }
-        }
+    /// Returns an http method from a verb, which is case sensitive
+    /// A verb that is not a token is 400 Bad Request
+    pub fn new(verb: &str) -> Result<Self, HttpError> {
+        let http_method = match verb {
+            "GET" => HttpMethod::Get,
+            "HEAD" => HttpMethod::Head,
+            "POST" => HttpMethod::Post,
+            "PUT" => HttpMethod::Put,
+            "DELETE" => HttpMethod::Delete,
+            "CONNECT" => HttpMethod::Connect,
+            "OPTIONS" => HttpMethod::Options,
+            "TRACE" => HttpMethod::Trace,
+            "PATCH" => HttpMethod::Patch,
+            _ if is_token(verb) => HttpMethod::Other(verb.to_string()),
+            _ => return Err(HttpError::bad_request("Invalid http method")),
+        };
 
-        Err(Error::new(ErrorKind::InvalidInput, "Invalid http method"))
+        Ok(http_method)
     }
 
     /// Returns the verb of the http method
-    pub fn get_verb(&self) -> &String {
-        &self.verb
+    pub fn get_verb(&self) -> &str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Delete => "DELETE",
+            HttpMethod::Connect => "CONNECT",
+            HttpMethod::Options => "OPTIONS",
+            HttpMethod::Trace => "TRACE",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Other(verb) => verb,
+        }
+    }
+
+    /// Returns true if the method only reads, like GET
+    /// Extension methods are not known to be safe
+    pub fn is_safe(&self) -> bool {
+        matches!(
+            self,
+            HttpMethod::Get | HttpMethod::Head | HttpMethod::Options | HttpMethod::Trace
+        )
+    }
+
+    /// Returns true if sending the request several times has the same effect as once, like PUT
+    pub fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self, HttpMethod::Put | HttpMethod::Delete)
+    }
+
+    /// Returns true if the method is not one of the standard ones
+    pub fn is_extension(&self) -> bool {
+        matches!(self, HttpMethod::Other(_))
+    }
+}
+
+impl fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_verb())
     }
 }
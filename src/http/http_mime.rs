@@ -0,0 +1,120 @@
+// Copyright 2023 Camilo Suárez Sandí
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The type of the files with an unknown extension
+pub const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
+
+/// The types of the common file extensions
+static MIME_TYPES: [(&str, &str); 38] = [
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("log", "text/plain"),
+    ("m4a", "audio/mp4"),
+    ("md", "text/markdown"),
+    ("mjs", "text/javascript"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("oga", "audio/ogg"),
+    ("ogv", "video/ogg"),
+    ("otf", "font/otf"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+    ("tar", "application/x-tar"),
+    ("ttf", "font/ttf"),
+    ("txt", "text/plain"),
+    ("wasm", "application/wasm"),
+    ("wav", "audio/wav"),
+    ("webm", "video/webm"),
+    ("webmanifest", "application/manifest+json"),
+    ("webp", "image/webp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("xml", "application/xml"),
+    ("zip", "application/zip"),
+    ("7z", "application/x-7z-compressed"),
+];
+
+/// The types that are text, even if they are not text/*
+static TEXT_TYPES: [&str; 4] = [
+    "application/json",
+    "application/manifest+json",
+    "application/xml",
+    "image/svg+xml",
+];
+
+/// Finds the media type of a file from its extension, like text/css for main.css
+#[derive(Clone)]
+pub struct MimeTypes {
+    /// The lowercase extensions, without the dot, and their types
+    types: HashMap<String, String>,
+}
+
+impl MimeTypes {
+    /// MimeTypes constructor
+    /// Returns the table of the common extensions
+    pub fn new() -> Self {
+        let types = MIME_TYPES
+            .iter()
+            .map(|(extension, mime_type)| (extension.to_string(), mime_type.to_string()))
+            .collect();
+
+        Self { types }
+    }
+
+    /// Adds or replaces the type of an extension, like ("log", "text/plain")
+    /// A type with parameters, like a charset, is sent as it is
+    pub fn set(&mut self, extension: &str, mime_type: &str) {
+        let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+
+        self.types.insert(extension, mime_type.to_string());
+    }
+
+    /// Returns the type of an extension, ignoring its case
+    pub fn get(&self, extension: &str) -> Option<&str> {
+        self.types
+            .get(&extension.to_ascii_lowercase())
+            .map(|mime_type| mime_type.as_str())
+    }
+
+    /// Returns the value of the Content-Type header for a file
+    /// Text types are sent as UTF-8, unknown extensions as raw bytes
+    pub fn content_type(&self, path: &Path) -> String {
+        let mime_type = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| self.get(extension))
+            .unwrap_or(DEFAULT_MIME_TYPE);
+
+        if Self::is_text(mime_type) {
+            format!("{}; charset=utf-8", mime_type)
+        } else {
+            mime_type.to_string()
+        }
+    }
+
+    /// Returns true if a type without parameters is text
+    fn is_text(mime_type: &str) -> bool {
+        !mime_type.contains(';')
+            && (mime_type.starts_with("text/") || TEXT_TYPES.contains(&mime_type))
+    }
+}
+
+impl Default for MimeTypes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright 2023 Camilo Suárez Sandí
+
+/// The decoded keys and values of a query string or an url encoded form
+pub struct HttpQuery {
+    /// The pairs, in the order they were sent, repeated keys are kept
+    pairs: Vec<(String, String)>,
+}
+
+impl HttpQuery {
+    /// Empty constructor
+    pub fn new() -> Self {
+        Self { pairs: Vec::new() }
+    }
+
+    /// HttpQuery constructor
+    /// Returns the pairs of a string like text=buy+milk&done=false
+    pub fn parse(string: &str) -> Self {
+        let pairs = string
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+
+                (Self::decode(key), Self::decode(value))
+            })
+            .collect();
+
+        Self { pairs }
+    }
+
+    /// Returns the first value of a key
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns all the values of a key, in order
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.pairs
+            .iter()
+            .filter(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    /// Returns true if the key was sent, even without a value
+    pub fn contains(&self, key: &str) -> bool {
+        self.pairs.iter().any(|(name, _)| name == key)
+    }
+
+    /// Getter for the pairs
+    pub fn get_pairs(&self) -> &Vec<(String, String)> {
+        &self.pairs
+    }
+
+    /// Decodes a key or a value, a + is a space in this format
+    fn decode(string: &str) -> String {
+        percent_decode(&string.replace('+', " "))
+    }
+}
+
+impl Default for HttpQuery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Replaces every byte that is not a letter, a digit or one of -._~ by its %XX sequence
+pub fn percent_encode(string: &str) -> String {
+    let mut encoded = String::with_capacity(string.len());
+
+    for byte in string.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    encoded
+}
+
+/// Replaces every %XX sequence by the byte it encodes
+/// Invalid sequences are kept as they are, and invalid UTF-8 is replaced
+pub fn percent_decode(string: &str) -> String {
+    let bytes = string.as_bytes();
+
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+
+            decoded.push(u8::from_str_radix(hex, 16).unwrap_or_default());
+            i += 3;
+            continue;
+        }
+
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decode_decodes_sequences() {
+        assert_eq!(percent_decode("a%20b%2Fc"), "a b/c");
+        assert_eq!(percent_decode("%41%4a%4A"), "AJJ");
+    }
+
+    #[test]
+    fn percent_decode_decodes_utf8() {
+        assert_eq!(percent_decode("a%C3%B1o"), "año");
+    }
+
+    #[test]
+    fn percent_decode_keeps_truncated_sequences() {
+        assert_eq!(percent_decode("%"), "%");
+        assert_eq!(percent_decode("%4"), "%4");
+        assert_eq!(percent_decode("a%"), "a%");
+        assert_eq!(percent_decode("a%4"), "a%4");
+        assert_eq!(percent_decode("%%41"), "%A");
+    }
+
+    #[test]
+    fn percent_decode_keeps_invalid_sequences() {
+        assert_eq!(percent_decode("%zz%4g%-1"), "%zz%4g%-1");
+    }
+
+    #[test]
+    fn percent_decode_replaces_invalid_utf8() {
+        assert_eq!(percent_decode("a%FFb"), "a\u{FFFD}b");
+        assert_eq!(percent_decode("%C3"), "\u{FFFD}");
+    }
+
+    #[test]
+    fn percent_decode_keeps_plus_signs() {
+        assert_eq!(percent_decode("a+b"), "a+b");
+    }
+
+    #[test]
+    fn parse_decodes_plus_signs_as_spaces() {
+        let query = HttpQuery::parse("text=buy+milk%2B&empty=&flag&&text=2");
+
+        assert_eq!(query.get("text"), Some("buy milk+"));
+        assert_eq!(query.get_all("text"), vec!["buy milk+", "2"]);
+        assert_eq!(query.get("empty"), Some(""));
+        assert!(query.contains("flag"));
+        assert_eq!(query.get_pairs().len(), 4);
+    }
+
+    #[test]
+    fn parse_keeps_equal_signs_in_values() {
+        assert_eq!(HttpQuery::parse("a=b=c").get("a"), Some("b=c"));
+    }
+
+    #[test]
+    fn percent_encode_keeps_unreserved_characters() {
+        assert_eq!(percent_encode("aZ09-._~"), "aZ09-._~");
+    }
+
+    #[test]
+    fn percent_encode_escapes_separators_and_utf8() {
+        assert_eq!(percent_encode("a b/c?ñ"), "a%20b%2Fc%3F%C3%B1");
+    }
+
+    #[test]
+    fn percent_encode_is_undone_by_percent_decode() {
+        let string = "docs/año 2023?x=1&y=%";
+
+        assert_eq!(percent_decode(&percent_encode(string)), string);
+    }
+}
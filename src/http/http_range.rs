@@ -0,0 +1,229 @@
+// Copyright 2023 Camilo Suárez Sandí
+
+/// The maximum number of ranges served from one request, more are answered with the whole file
+pub const MAX_RANGES: usize = 16;
+
+/// A range of bytes of a file, both ends included, like 0-499 for the first 500 bytes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ByteRange {
+    /// The first byte
+    start: u64,
+    /// The last byte
+    end: u64,
+}
+
+impl ByteRange {
+    /// Getter for the start
+    pub fn get_start(&self) -> u64 {
+        self.start
+    }
+
+    /// Getter for the end
+    pub fn get_end(&self) -> u64 {
+        self.end
+    }
+
+    /// Returns the number of bytes of the range
+    pub fn get_length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// Returns the value of the Content-Range header, like bytes 0-499/1234
+    pub fn content_range(&self, length: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, length)
+    }
+}
+
+/// Parses a Range header, like bytes=0-499,-500, for a file of length bytes
+/// Returns None if the header is invalid or is not in bytes, so it has to be ignored
+/// Returns no ranges if none of them is satisfiable
+pub fn parse_ranges(header: &str, length: u64) -> Option<Vec<ByteRange>> {
+    let (unit, set) = header.split_once('=')?;
+
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+
+    let specs: Vec<&str> = set
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+        .collect();
+
+    if specs.is_empty() || specs.len() > MAX_RANGES {
+        return None;
+    }
+
+    let mut ranges = Vec::new();
+
+    for spec in specs {
+        let (first, last) = spec.split_once('-')?;
+
+        let range = match (first.trim(), last.trim()) {
+            ("", suffix) => {
+                // The last bytes of the file, like -500
+                let suffix = parse_position(suffix)?;
+
+                if suffix == 0 || length == 0 {
+                    continue;
+                }
+
+                ByteRange {
+                    start: length.saturating_sub(suffix),
+                    end: length - 1,
+                }
+            }
+            (first, last) => {
+                let start = parse_position(first)?;
+
+                let end = match last {
+                    "" => u64::MAX,
+                    last => parse_position(last)?,
+                };
+
+                if end < start {
+                    return None;
+                }
+
+                if start >= length {
+                    continue;
+                }
+
+                ByteRange {
+                    start,
+                    end: end.min(length - 1),
+                }
+            }
+        };
+
+        ranges.push(range);
+    }
+
+    Some(ranges)
+}
+
+/// Parses a position of a range, only digits are allowed
+fn parse_position(value: &str) -> Option<u64> {
+    if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the start and the end of each range
+    fn ends(header: &str, length: u64) -> Option<Vec<(u64, u64)>> {
+        parse_ranges(header, length).map(|ranges| {
+            ranges
+                .iter()
+                .map(|range| (range.get_start(), range.get_end()))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn closed_range() {
+        assert_eq!(ends("bytes=0-499", 1000), Some(vec![(0, 499)]));
+    }
+
+    #[test]
+    fn closed_range_is_cut_at_the_end_of_the_file() {
+        assert_eq!(ends("bytes=900-1999", 1000), Some(vec![(900, 999)]));
+    }
+
+    #[test]
+    fn open_ended_range() {
+        assert_eq!(ends("bytes=100-", 1000), Some(vec![(100, 999)]));
+    }
+
+    #[test]
+    fn suffix_range() {
+        assert_eq!(ends("bytes=-500", 1000), Some(vec![(500, 999)]));
+    }
+
+    #[test]
+    fn suffix_longer_than_the_file_is_the_whole_file() {
+        assert_eq!(ends("bytes=-5000", 1000), Some(vec![(0, 999)]));
+    }
+
+    #[test]
+    fn several_ranges_keep_their_order() {
+        assert_eq!(
+            ends("bytes=500-599, 0-99,-10", 1000),
+            Some(vec![(500, 599), (0, 99), (990, 999)])
+        );
+    }
+
+    #[test]
+    fn unit_and_spaces_are_flexible() {
+        assert_eq!(ends(" Bytes = 0-9 ", 1000), Some(vec![(0, 9)]));
+    }
+
+    #[test]
+    fn range_after_the_end_is_unsatisfiable() {
+        assert_eq!(ends("bytes=1000-1999", 1000), Some(vec![]));
+    }
+
+    #[test]
+    fn empty_suffix_is_unsatisfiable() {
+        assert_eq!(ends("bytes=-0", 1000), Some(vec![]));
+    }
+
+    #[test]
+    fn any_range_of_an_empty_file_is_unsatisfiable() {
+        assert_eq!(ends("bytes=0-,-10", 0), Some(vec![]));
+    }
+
+    #[test]
+    fn unsatisfiable_ranges_are_skipped_among_valid_ones() {
+        assert_eq!(ends("bytes=2000-,0-9", 1000), Some(vec![(0, 9)]));
+    }
+
+    #[test]
+    fn ranges_up_to_the_limit_are_served() {
+        let specs = vec!["0-0"; MAX_RANGES].join(",");
+
+        assert_eq!(
+            parse_ranges(&format!("bytes={}", specs), 1000).map(|ranges| ranges.len()),
+            Some(MAX_RANGES)
+        );
+    }
+
+    #[test]
+    fn ranges_over_the_limit_are_ignored() {
+        let specs = vec!["0-0"; MAX_RANGES + 1].join(",");
+
+        assert_eq!(ends(&format!("bytes={}", specs), 1000), None);
+    }
+
+    #[test]
+    fn invalid_headers_are_ignored() {
+        for header in [
+            "items=0-9",
+            "bytes",
+            "bytes=",
+            "bytes=,",
+            "bytes=5",
+            "bytes=9-0",
+            "bytes=a-9",
+            "bytes=+1-9",
+            "bytes=0-9,x",
+            "bytes=--5",
+            "bytes=99999999999999999999-",
+        ] {
+            assert_eq!(ends(header, 1000), None, "{}", header);
+        }
+    }
+
+    #[test]
+    fn length_and_content_range() {
+        let range = parse_ranges("bytes=10-19", 1000).unwrap()[0];
+
+        assert_eq!(range.get_length(), 10);
+        assert_eq!(range.content_range(1000), "bytes 10-19/1000");
+    }
+}
@@ -1,34 +1,72 @@
 // Copyright 2023 Camilo Suárez Sandí
 
-use std::collections::HashMap;
 use std::io::BufRead;
-use std::io::BufReader;
-use std::io::Error;
 use std::io::ErrorKind;
-use std::net::TcpStream;
+use std::io::Read;
 
+use crate::http::http_config::HttpConfig;
+use crate::http::http_error::HttpError;
+use crate::http::http_headers::is_token;
+use crate::http::http_headers::HeaderMap;
 use crate::http::http_method::HttpMethod;
+use crate::http::http_query::HttpQuery;
+use crate::http::http_status::StatusCode;
+
+/// The maximum length of a chunk size line or a trailer line, in bytes
+const MAX_CHUNK_LINE_LENGTH: u64 = 4096;
+
+/// Headers that a client is not allowed to send as trailers
+static FORBIDDEN_TRAILERS: [&str; 6] = [
+    "Content-Length",
+    "Transfer-Encoding",
+    "Content-Encoding",
+    "Content-Type",
+    "Host",
+    "Trailer",
+];
+
+/// The versions of http the server answers
+static HTTP_VERSIONS: [&str; 2] = ["HTTP/1.0", "HTTP/1.1"];
+
+/// The forms of the target of a request line
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TargetForm {
+    /// A path and a query, like /tasks?id=1
+    Origin,
+    /// A whole url, like http://example.com/tasks, it is sent to proxies
+    Absolute,
+    /// A host and a port, like example.com:443, only for CONNECT
+    Authority,
+    /// Only *, for OPTIONS about the whole server
+    Asterisk,
+}
 
 /// An http request
 pub struct HttpRequest {
     /// An http method, like GET or POST
     method: HttpMethod,
-    /// A url
+    /// A path and a query, an absolute url is reduced to them and its authority becomes the Host
     target: String,
+    /// The form the target was sent in
+    target_form: TargetForm,
     /// The http version
     version: String,
     /// Information for the server
-    headers: HashMap<String, String>,
+    headers: HeaderMap,
+    /// The body, as sent by the client
+    body: Vec<u8>,
 }
 
 impl HttpRequest {
     /// Empty constructor
     pub fn new() -> Self {
         HttpRequest {
-            method: HttpMethod::new("GET").unwrap(),
+            method: HttpMethod::Get,
             target: "/".to_string(),
+            target_form: TargetForm::Origin,
             version: "HTTP/1.1".to_string(),
-            headers: HashMap::new(),
+            headers: HeaderMap::new(),
+            body: Vec::new(),
         }
     }
 
@@ -42,86 +80,868 @@ impl HttpRequest {
         &self.target
     }
 
+    /// Getter for the target form
+    pub fn get_target_form(&self) -> TargetForm {
+        self.target_form
+    }
+
+    /// Returns the path of the target, without the query string
+    pub fn get_path(&self) -> &str {
+        self.target
+            .split_once('?')
+            .map_or(self.target.as_str(), |(path, _)| path)
+    }
+
+    /// Returns the query string of the target, without the ?
+    pub fn get_query_string(&self) -> &str {
+        self.target.split_once('?').map_or("", |(_, query)| query)
+    }
+
+    /// Returns the decoded query of the target
+    pub fn get_query(&self) -> HttpQuery {
+        HttpQuery::parse(self.get_query_string())
+    }
+
+    /// Returns the decoded body of an url encoded form
+    /// The query is empty if the body has another Content-Type
+    pub fn get_form(&self) -> HttpQuery {
+        let media_type = self.headers.get_content_type().unwrap_or_default();
+
+        if !media_type.eq_ignore_ascii_case("application/x-www-form-urlencoded") {
+            return HttpQuery::new();
+        }
+
+        HttpQuery::parse(&String::from_utf8_lossy(&self.body))
+    }
+
     /// Getter for the version
     pub fn get_version(&self) -> &String {
         &self.version
     }
 
     /// Getter for the headers
-    pub fn get_headers(&self) -> &HashMap<String, String> {
+    pub fn get_headers(&self) -> &HeaderMap {
         &self.headers
     }
 
+    /// Returns the first value of a header, ignoring the case of its name
+    pub fn get_header(&self, key: &str) -> Option<&str> {
+        self.headers.get(key)
+    }
+
+    /// Getter for the body
+    pub fn get_body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Returns true if the client wants to keep the connection open after the response
+    /// HTTP/1.1 connections are persistent by default, older versions have to ask for it
+    pub fn is_keep_alive(&self) -> bool {
+        if self.version == "HTTP/1.1" {
+            !self.headers.has_token("Connection", "close")
+        } else {
+            self.headers.has_token("Connection", "keep-alive")
+        }
+    }
+
     /// HttpRequest constructor
-    /// Returns an http request from a TcpStream
-    pub fn from_stream(stream: &TcpStream) -> Result<Self, Error> {
-        let buf_reader = BufReader::new(stream);
+    /// Returns an http request from a buffered reader, or None if the reader is at its end
+    /// The reader is left right after the body, so it can be used for the next request
+    pub fn from_reader<R: BufRead>(
+        reader: &mut R,
+        config: &HttpConfig,
+    ) -> Result<Option<Self>, HttpError> {
+        let http_request = match Self::head_from_reader(reader, config)? {
+            Some(mut http_request) => {
+                http_request.read_body(reader, config)?;
+
+                http_request
+            }
+            None => return Ok(None),
+        };
 
-        let lines: Vec<String> = buf_reader
-            .lines()
-            .map(|line| line.unwrap_or_default())
-            .take_while(|line| !line.is_empty())
-            .collect();
+        Ok(Some(http_request))
+    }
+
+    /// HttpRequest constructor
+    /// Returns an http request with the request line and the headers, but without the body
+    /// The reader is left at the start of the body, which is read with read_body
+    pub fn head_from_reader<R: BufRead>(
+        reader: &mut R,
+        config: &HttpConfig,
+    ) -> Result<Option<Self>, HttpError> {
+        let lines = Self::read_head(reader, config)?;
 
         if lines.is_empty() {
-            return Ok(HttpRequest::new());
+            return Ok(None);
         }
 
         let mut http_request = Self::from_first_line(&lines[0])?;
 
-        // TODO: Remove
-        println!(
-            "Request: {} {}",
-            http_request.get_method().get_verb(),
-            http_request.get_target()
-        );
-
         http_request.add_headers_from_lines(&lines[1..])?;
 
-        Ok(http_request)
+        http_request.check_host()?;
+
+        Ok(Some(http_request))
+    }
+
+    /// Reads the lines of the request line and the headers, up to the first empty line
+    /// The request line and the headers can not go over the limits of the settings
+    fn read_head<R: BufRead>(
+        reader: &mut R,
+        config: &HttpConfig,
+    ) -> Result<Vec<String>, HttpError> {
+        let mut lines = Vec::new();
+
+        let mut header_size = 0;
+
+        loop {
+            // The line break is not part of the limits
+            let limit = if lines.is_empty() {
+                config.get_max_request_line_length()
+            } else {
+                config.get_max_header_size().saturating_sub(header_size)
+            };
+
+            let mut line = String::new();
+
+            let read = reader
+                .take(limit as u64 + 2)
+                .read_line(&mut line)
+                .map_err(|error| {
+                    if error.kind() == ErrorKind::InvalidData {
+                        HttpError::bad_request("The head of the request is not UTF-8")
+                    } else {
+                        HttpError::from(error)
+                    }
+                })?;
+
+            // The client closed the connection before sending a request, or in the middle of one
+            if read == 0 && lines.is_empty() {
+                break;
+            }
+
+            if read == 0 {
+                return Err(HttpError::bad_request("Incomplete request head"));
+            }
+
+            let complete = line.ends_with('\n');
+
+            let line = line.trim_end_matches(['\r', '\n']);
+
+            if line.len() > limit {
+                return Err(if lines.is_empty() {
+                    HttpError::new(StatusCode::URI_TOO_LONG, "Request line too long")
+                } else {
+                    HttpError::new(
+                        StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+                        "Request headers too large",
+                    )
+                });
+            }
+
+            if !complete {
+                return Err(HttpError::bad_request("Incomplete request head"));
+            }
+
+            if !lines.is_empty() {
+                header_size += line.len();
+            }
+
+            // Empty lines before the request line are ignored, like the spec recommends
+            if line.is_empty() && lines.is_empty() {
+                continue;
+            }
+
+            if line.is_empty() {
+                break;
+            }
+
+            if lines.len() > config.get_max_header_count() {
+                return Err(HttpError::new(
+                    StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+                    "Too many request headers",
+                ));
+            }
+
+            lines.push(line.to_string());
+        }
+
+        Ok(lines)
+    }
+
+    /// Reads the body using the Transfer-Encoding or the Content-Length header
+    /// A request with both is rejected, since the end of its body is ambiguous
+    pub fn read_body<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        config: &HttpConfig,
+    ) -> Result<(), HttpError> {
+        if self.headers.contains("Transfer-Encoding") {
+            if self.headers.contains("Content-Length") {
+                return Err(HttpError::bad_request(
+                    "Transfer-Encoding and Content-Length headers together",
+                ));
+            }
+
+            let codings = self.headers.get_list("Transfer-Encoding");
+
+            if codings.len() != 1 || !codings[0].eq_ignore_ascii_case("chunked") {
+                return Err(HttpError::new(
+                    StatusCode::NOT_IMPLEMENTED,
+                    "Unsupported Transfer-Encoding header",
+                ));
+            }
+
+            return self.read_chunked_body(reader, config);
+        }
+
+        let length = match self.headers.get_content_length()? {
+            Some(length) => length,
+            None => return Ok(()),
+        };
+
+        if length > config.get_max_body_size() as u64 {
+            return Err(HttpError::new(
+                StatusCode::CONTENT_TOO_LARGE,
+                "Request body too large",
+            ));
+        }
+
+        let mut body = vec![0; length as usize];
+
+        reader.read_exact(&mut body)?;
+
+        self.body = body;
+
+        Ok(())
+    }
+
+    /// Reads a body sent with the chunked transfer coding, followed by its trailers
+    fn read_chunked_body<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        config: &HttpConfig,
+    ) -> Result<(), HttpError> {
+        let mut body = Vec::new();
+
+        loop {
+            let line = Self::read_chunk_line(reader)?;
+
+            // Chunk extensions are allowed but ignored
+            let size = line.split(';').next().unwrap_or_default().trim();
+
+            // Only hex digits, from_str_radix would also take a sign
+            if size.is_empty() || !size.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                return Err(HttpError::bad_request("Invalid chunk size"));
+            }
+
+            // A size too big for usize is too big for the body anyway
+            let size = usize::from_str_radix(size, 16).unwrap_or(usize::MAX);
+
+            if size == 0 {
+                break;
+            }
+
+            if size > config.get_max_body_size().saturating_sub(body.len()) {
+                return Err(HttpError::new(
+                    StatusCode::CONTENT_TOO_LARGE,
+                    "Request body too large",
+                ));
+            }
+
+            let start = body.len();
+
+            body.resize(start + size, 0);
+
+            reader.read_exact(&mut body[start..])?;
+
+            if !Self::read_chunk_line(reader)?.is_empty() {
+                return Err(HttpError::bad_request("Missing line break after a chunk"));
+            }
+        }
+
+        let mut trailers_count = 0;
+
+        loop {
+            let line = Self::read_chunk_line(reader)?;
+
+            if line.is_empty() {
+                break;
+            }
+
+            trailers_count += 1;
+
+            if trailers_count > config.get_max_header_count() {
+                return Err(HttpError::new(
+                    StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+                    "Too many trailers",
+                ));
+            }
+
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| HttpError::bad_request("Invalid trailer"))?;
+
+            let forbidden = FORBIDDEN_TRAILERS
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(key.trim()));
+
+            if !is_token(key) || value.chars().any(|c| c.is_ascii_control() && c != '\t') {
+                return Err(HttpError::bad_request("Invalid trailer"));
+            }
+
+            if !forbidden {
+                self.headers.append(key, value);
+            }
+        }
+
+        self.body = body;
+
+        Ok(())
+    }
+
+    /// Reads a single line inside a chunked body, without its line break
+    fn read_chunk_line<R: BufRead>(reader: &mut R) -> Result<String, HttpError> {
+        let mut line = String::new();
+
+        reader
+            .take(MAX_CHUNK_LINE_LENGTH)
+            .read_line(&mut line)
+            .map_err(|error| match error.kind() {
+                ErrorKind::InvalidData => HttpError::bad_request("Invalid chunk line"),
+                _ => HttpError::from(error),
+            })?;
+
+        if !line.ends_with('\n') {
+            return Err(HttpError::bad_request("Invalid chunk line"));
+        }
+
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
     }
 
     /// Reads the method, target and version
-    fn from_first_line(line: &str) -> Result<Self, Error> {
+    /// The parts are separated by single spaces, and can not have control characters
+    fn from_first_line(line: &str) -> Result<Self, HttpError> {
+        if line.chars().any(|c| c.is_ascii_control()) {
+            return Err(HttpError::bad_request("Invalid http request message"));
+        }
+
         let split: Vec<&str> = line.split(' ').collect();
 
         if split.len() != 3 {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "Invalid http request message",
-            ));
+            return Err(HttpError::bad_request("Invalid http request message"));
         }
 
-        let method = HttpMethod::new(&split[0])?;
-
-        // TODO: Validation of target
-        let target = split[1].to_string();
+        let method = HttpMethod::new(split[0])?;
 
-        // TODO: Validation of version
-        let version = split[2].to_string();
+        let version = Self::parse_version(split[2])?;
 
-        let http_request = Self {
+        let mut http_request = Self {
             method,
-            target,
+            target: String::new(),
+            target_form: TargetForm::Origin,
             version,
-            headers: HashMap::new(),
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+        };
+
+        http_request.parse_target(split[1])?;
+
+        Ok(http_request)
+    }
+
+    /// Returns the version of a request line, like HTTP/1.1
+    /// A well formed version that the server does not answer is 505 HTTP Version Not Supported
+    fn parse_version(version: &str) -> Result<String, HttpError> {
+        let numbers = version.strip_prefix("HTTP/").unwrap_or_default().as_bytes();
+
+        let well_formed = numbers.len() == 3
+            && numbers[0].is_ascii_digit()
+            && numbers[1] == b'.'
+            && numbers[2].is_ascii_digit();
+
+        if !well_formed {
+            return Err(HttpError::bad_request("Invalid http version"));
+        }
+
+        if !HTTP_VERSIONS.contains(&version) {
+            return Err(HttpError::new(
+                StatusCode::HTTP_VERSION_NOT_SUPPORTED,
+                "Unsupported http version",
+            ));
+        }
+
+        Ok(version.to_string())
+    }
+
+    /// Reads the target of a request line in any of its forms
+    /// The form has to fit the method, like * for OPTIONS or a host and a port for CONNECT
+    fn parse_target(&mut self, target: &str) -> Result<(), HttpError> {
+        let invalid = || HttpError::bad_request("Invalid request target");
+
+        if self.method == HttpMethod::Connect {
+            // A host and a port, without a path or user information
+            let (host, port) = target.rsplit_once(':').ok_or_else(invalid)?;
+
+            let valid = !host.is_empty()
+                && !host.contains(['/', '?', '#', '@'])
+                && !port.is_empty()
+                && port.bytes().all(|byte| byte.is_ascii_digit());
+
+            if !valid {
+                return Err(invalid());
+            }
+
+            self.target = target.to_string();
+            self.target_form = TargetForm::Authority;
+
+            return Ok(());
+        }
+
+        if target == "*" {
+            if self.method != HttpMethod::Options {
+                return Err(invalid());
+            }
+
+            self.target = target.to_string();
+            self.target_form = TargetForm::Asterisk;
+
+            return Ok(());
+        }
+
+        if target.starts_with('/') {
+            if target.contains('#') {
+                return Err(invalid());
+            }
+
+            self.target = target.to_string();
+            self.target_form = TargetForm::Origin;
+
+            return Ok(());
+        }
+
+        let rest = match target.split_once("://") {
+            Some((scheme, rest))
+                if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") =>
+            {
+                rest
+            }
+            _ => return Err(invalid()),
         };
 
-        return Ok(http_request);
+        let end = rest.find(['/', '?']).unwrap_or(rest.len());
+
+        let (authority, path) = rest.split_at(end);
+
+        if authority.is_empty() || authority.contains('@') || path.contains('#') {
+            return Err(invalid());
+        }
+
+        // The authority of an absolute target takes the place of the Host header
+        self.headers.insert("Host", authority);
+
+        self.target = if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("/{}", path)
+        };
+        self.target_form = TargetForm::Absolute;
+
+        Ok(())
+    }
+
+    /// Checks the Host header, HTTP/1.1 requests must have exactly one
+    fn check_host(&self) -> Result<(), HttpError> {
+        let hosts = self.headers.get_all("Host");
+
+        if hosts.len() > 1 {
+            return Err(HttpError::bad_request("Invalid Host header"));
+        }
+
+        if hosts.is_empty() && self.version == "HTTP/1.1" {
+            return Err(HttpError::bad_request("Missing Host header"));
+        }
+
+        Ok(())
     }
 
     /// Reads and adds the headers
-    fn add_headers_from_lines(&mut self, lines: &[String]) -> Result<(), Error> {
+    fn add_headers_from_lines(&mut self, lines: &[String]) -> Result<(), HttpError> {
         for line in lines {
-            if !line.contains(':') {
-                return Err(Error::new(ErrorKind::InvalidInput, "Invalid http header"));
+            // A name with spaces, or a line folded from the previous one, is rejected
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) if is_token(key) => (key, value),
+                _ => return Err(HttpError::bad_request("Invalid http header")),
+            };
+
+            if value.chars().any(|c| c.is_ascii_control() && c != '\t') {
+                return Err(HttpError::bad_request("Invalid http header"));
             }
 
-            let (key, value) = line.split_once(':').unwrap_or_default();
+            // The Host of an absolute target was already set, and the one sent is ignored
+            if self.target_form == TargetForm::Absolute && key.eq_ignore_ascii_case("Host") {
+                continue;
+            }
 
-            self.headers.insert(key.to_string(), value.to_string());
+            self.headers.append(key, value);
         }
 
         Ok(())
     }
 }
+
+impl Default for HttpRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Reads a request with the default settings
+    fn read(raw: &str) -> Result<Option<HttpRequest>, HttpError> {
+        HttpRequest::from_reader(&mut Cursor::new(raw), &HttpConfig::new())
+    }
+
+    /// Returns the code of the error of a request that can not be read
+    fn error_code(raw: &str) -> StatusCode {
+        read(raw).err().unwrap().get_code()
+    }
+
+    #[test]
+    fn unsupported_version_is_505() {
+        assert_eq!(
+            error_code("GET / HTTP/2.0\r\nHost: localhost\r\n\r\n"),
+            StatusCode::HTTP_VERSION_NOT_SUPPORTED
+        );
+    }
+
+    #[test]
+    fn malformed_versions_are_bad_requests() {
+        for version in ["http/1.1", "HTTP/1", "HTTP/1.10", "HTTP/a.b", "HTTP1.1", ""] {
+            let raw = format!("GET / {}\r\nHost: localhost\r\n\r\n", version);
+
+            assert_eq!(error_code(&raw), StatusCode::BAD_REQUEST, "{}", version);
+        }
+    }
+
+    #[test]
+    fn http_1_0_is_accepted() {
+        let http_request = read("GET / HTTP/1.0\r\n\r\n").ok().flatten().unwrap();
+
+        assert_eq!(http_request.get_version(), "HTTP/1.0");
+    }
+
+    #[test]
+    fn request_line_needs_single_spaces() {
+        for line in [
+            "GET  / HTTP/1.1",
+            "GET / HTTP/1.1 ",
+            "GET /",
+            "GET\t/ HTTP/1.1",
+        ] {
+            let raw = format!("{}\r\nHost: localhost\r\n\r\n", line);
+
+            assert_eq!(error_code(&raw), StatusCode::BAD_REQUEST, "{}", line);
+        }
+    }
+
+    #[test]
+    fn absolute_target_becomes_the_path_and_the_host() {
+        let http_request =
+            read("GET http://example.com:8080/tasks?id=1 HTTP/1.1\r\nHost: other\r\n\r\n")
+                .ok()
+                .flatten()
+                .unwrap();
+
+        assert_eq!(http_request.get_target(), "/tasks?id=1");
+        assert_eq!(http_request.get_target_form(), TargetForm::Absolute);
+        assert_eq!(http_request.get_header("Host"), Some("example.com:8080"));
+        assert_eq!(http_request.get_headers().get_all("Host").len(), 1);
+    }
+
+    #[test]
+    fn absolute_target_without_a_path_is_the_root() {
+        let http_request = read("GET https://example.com?a=b HTTP/1.1\r\n\r\n")
+            .ok()
+            .flatten()
+            .unwrap();
+
+        assert_eq!(http_request.get_target(), "/?a=b");
+        assert_eq!(http_request.get_header("Host"), Some("example.com"));
+    }
+
+    #[test]
+    fn invalid_absolute_targets_are_bad_requests() {
+        for target in [
+            "ftp://example.com/",
+            "http:///path",
+            "http://user@example.com/",
+            "tasks",
+        ] {
+            let raw = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target);
+
+            assert_eq!(error_code(&raw), StatusCode::BAD_REQUEST, "{}", target);
+        }
+    }
+
+    #[test]
+    fn connect_takes_an_authority() {
+        let http_request = read("CONNECT example.com:443 HTTP/1.1\r\nHost: example.com\r\n\r\n")
+            .ok()
+            .flatten()
+            .unwrap();
+
+        assert_eq!(http_request.get_target(), "example.com:443");
+        assert_eq!(http_request.get_target_form(), TargetForm::Authority);
+    }
+
+    #[test]
+    fn connect_without_an_authority_is_a_bad_request() {
+        for target in [
+            "/",
+            "example.com",
+            "example.com:",
+            ":443",
+            "user@example.com:443",
+            "example.com:https",
+        ] {
+            let raw = format!("CONNECT {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target);
+
+            assert_eq!(error_code(&raw), StatusCode::BAD_REQUEST, "{}", target);
+        }
+    }
+
+    #[test]
+    fn asterisk_is_only_for_options() {
+        let http_request = read("OPTIONS * HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .ok()
+            .flatten()
+            .unwrap();
+
+        assert_eq!(http_request.get_target_form(), TargetForm::Asterisk);
+
+        assert_eq!(
+            error_code("GET * HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn fragments_are_bad_requests() {
+        assert_eq!(
+            error_code("GET /tasks#top HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            error_code("GET http://example.com/#top HTTP/1.1\r\n\r\n"),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn control_characters_are_bad_requests() {
+        for raw in [
+            "GET /a\x01b HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            "GET /a\x7fb HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            "GET / HTTP/1.1\r\nHost: localhost\r\nX-Name: a\x00b\r\n\r\n",
+        ] {
+            assert_eq!(error_code(raw), StatusCode::BAD_REQUEST, "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn tabs_are_allowed_in_header_values() {
+        let http_request = read("GET / HTTP/1.1\r\nHost: localhost\r\nX-Name: a\tb\r\n\r\n")
+            .ok()
+            .flatten()
+            .unwrap();
+
+        assert_eq!(http_request.get_header("X-Name"), Some("a\tb"));
+    }
+
+    #[test]
+    fn host_is_required_by_http_1_1_only() {
+        assert_eq!(
+            error_code("GET / HTTP/1.1\r\n\r\n"),
+            StatusCode::BAD_REQUEST
+        );
+        assert!(read("GET / HTTP/1.0\r\n\r\n").ok().flatten().is_some());
+    }
+
+    #[test]
+    fn repeated_host_is_a_bad_request() {
+        for version in ["HTTP/1.1", "HTTP/1.0"] {
+            let raw = format!("GET / {}\r\nHost: a\r\nHost: b\r\n\r\n", version);
+
+            assert_eq!(error_code(&raw), StatusCode::BAD_REQUEST, "{}", version);
+        }
+    }
+
+    #[test]
+    fn empty_stream_has_no_request() {
+        assert!(read("").ok().unwrap().is_none());
+        assert!(read("\r\n").ok().unwrap().is_none());
+    }
+
+    #[test]
+    fn head_cut_before_the_empty_line_is_a_bad_request() {
+        let error = read("GET / HTTP/1.1\r\nHost: localhost\r\n").err().unwrap();
+
+        assert_eq!(error.get_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn header_cut_before_its_line_break_is_a_bad_request() {
+        let error = read("GET / HTTP/1.1\r\nHost: localhost").err().unwrap();
+
+        assert_eq!(error.get_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn request_line_cut_before_its_line_break_is_a_bad_request() {
+        let error = read("GET / HTTP/1.1").err().unwrap();
+
+        assert_eq!(error.get_code(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Reads a chunked POST request with a body
+    fn read_chunked(body: &str, max_body_size: usize) -> Result<HttpRequest, HttpError> {
+        let raw = format!(
+            "POST / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n{}",
+            body
+        );
+
+        let mut config = HttpConfig::new();
+
+        config.set_max_body_size(max_body_size);
+
+        HttpRequest::from_reader(&mut Cursor::new(raw), &config).map(Option::unwrap)
+    }
+
+    #[test]
+    fn chunked_body_is_decoded() {
+        let http_request =
+            read_chunked("5\r\nhello\r\n6;name=value\r\n world\r\n0\r\n\r\n", 64).unwrap();
+
+        assert_eq!(http_request.get_body(), b"hello world");
+    }
+
+    #[test]
+    fn chunked_trailers_are_added_except_forbidden_ones() {
+        let http_request = read_chunked(
+            "2\r\nhi\r\n0\r\nExpires: never\r\nContent-Length: 2\r\n\r\n",
+            64,
+        )
+        .unwrap();
+
+        assert_eq!(http_request.get_header("Expires"), Some("never"));
+        assert_eq!(http_request.get_header("Content-Length"), None);
+    }
+
+    #[test]
+    fn chunked_trailer_with_control_characters_is_rejected() {
+        let error = read_chunked("0\r\nExpires: a\x01b\r\n\r\n", 64)
+            .err()
+            .unwrap();
+
+        assert_eq!(error.get_code(), StatusCode::BAD_REQUEST);
+
+        let http_request = read_chunked("0\r\nExpires: a\tb\r\n\r\n", 64).unwrap();
+
+        assert_eq!(http_request.get_header("Expires"), Some("a\tb"));
+    }
+
+    #[test]
+    fn chunked_body_leaves_the_next_request() {
+        let raw = "POST / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n\
+                   2\r\nhi\r\n0\r\n\r\nGET /next HTTP/1.1\r\nHost: localhost\r\n\r\n";
+
+        let mut reader = Cursor::new(raw);
+
+        let config = HttpConfig::new();
+
+        let first = HttpRequest::from_reader(&mut reader, &config)
+            .unwrap()
+            .unwrap();
+        let second = HttpRequest::from_reader(&mut reader, &config)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(first.get_body(), b"hi");
+        assert_eq!(second.get_target(), "/next");
+    }
+
+    #[test]
+    fn chunked_body_with_content_length_is_a_bad_request() {
+        let raw = "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 40\r\n\
+                   Transfer-Encoding: chunked\r\n\r\n0\r\n\r\n";
+
+        let error = HttpRequest::from_reader(&mut Cursor::new(raw), &HttpConfig::new())
+            .err()
+            .unwrap();
+
+        assert_eq!(error.get_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn chunk_size_over_the_limit_is_too_large() {
+        let error = read_chunked("5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n", 8)
+            .err()
+            .unwrap();
+
+        assert_eq!(error.get_code(), StatusCode::CONTENT_TOO_LARGE);
+    }
+
+    #[test]
+    fn huge_chunk_size_does_not_overflow() {
+        let error = read_chunked("5\r\nhello\r\nfffffffffffffffe\r\n", 64)
+            .err()
+            .unwrap();
+
+        assert_eq!(error.get_code(), StatusCode::CONTENT_TOO_LARGE);
+    }
+
+    #[test]
+    fn chunk_size_bigger_than_usize_is_too_large() {
+        let error = read_chunked("ffffffffffffffffffffffffffffffff\r\n", 64)
+            .err()
+            .unwrap();
+
+        assert_eq!(error.get_code(), StatusCode::CONTENT_TOO_LARGE);
+    }
+
+    #[test]
+    fn malformed_chunk_sizes_are_bad_requests() {
+        for body in [
+            "+5\r\nhello\r\n0\r\n\r\n",
+            "-1\r\n",
+            "zz\r\n",
+            "\r\n",
+            "0x5\r\n",
+        ] {
+            let error = read_chunked(body, 64).err().unwrap();
+
+            assert_eq!(error.get_code(), StatusCode::BAD_REQUEST, "{:?}", body);
+        }
+    }
+
+    #[test]
+    fn chunk_without_line_break_is_a_bad_request() {
+        let error = read_chunked("5\r\nhelloX\r\n0\r\n\r\n", 64).err().unwrap();
+
+        assert_eq!(error.get_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn chunk_size_line_without_line_break_is_a_bad_request() {
+        let error = read_chunked("5", 64).err().unwrap();
+
+        assert_eq!(error.get_code(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,19 +1,71 @@
 // Copyright 2022 Camilo Suárez Sandí
 
-use std::collections::HashMap;
+use std::io::Error;
+use std::io::Read;
+use std::io::Write;
+use std::time::SystemTime;
+
+use crate::http::http_headers::HeaderMap;
+use crate::http::http_status::StatusCode;
+
+/// The version used when none was set
+const DEFAULT_VERSION: &str = "HTTP/1.1";
+
+/// The size of each chunk when a body is sent with the chunked transfer coding
+const CHUNK_SIZE: usize = 8192;
+
+/// The kinds of redirection, they differ in how long they last and if the method is kept
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RedirectKind {
+    /// 301, the client can change the method to GET
+    MovedPermanently,
+    /// 302, the client can change the method to GET
+    Found,
+    /// 303, the client has to use GET, like after a form
+    SeeOther,
+    /// 307, the client keeps the method
+    TemporaryRedirect,
+    /// 308, the client keeps the method
+    PermanentRedirect,
+}
+
+impl RedirectKind {
+    /// Returns the status code of the redirection
+    pub fn get_code(&self) -> StatusCode {
+        match self {
+            RedirectKind::MovedPermanently => StatusCode::MOVED_PERMANENTLY,
+            RedirectKind::Found => StatusCode::FOUND,
+            RedirectKind::SeeOther => StatusCode::SEE_OTHER,
+            RedirectKind::TemporaryRedirect => StatusCode::TEMPORARY_REDIRECT,
+            RedirectKind::PermanentRedirect => StatusCode::PERMANENT_REDIRECT,
+        }
+    }
+}
+
+/// A body that is read while it is sent, instead of being kept in memory
+struct BodyReader {
+    /// The source of the body
+    reader: Box<dyn Read + Send>,
+    /// The size of the body, if it is known
+    length: Option<u64>,
+}
 
 /// An http response
 pub struct HttpResponse {
     /// The http version
     version: String,
-    /// An http code, like 200 or 404
-    code: usize,
+    /// A status code, like 200 or 404
+    code: StatusCode,
     /// An http message, like OK
     message: String,
     /// Information for the server
-    headers: HashMap<String, String>,
-    /// The body
-    body: String,
+    headers: HeaderMap,
+    /// The body, as raw bytes
+    body: Vec<u8>,
+    /// A body to read while sending, it takes the place of the raw bytes
+    body_reader: Option<BodyReader>,
+    /// If the body is left out when the response is sent, like for HEAD
+    body_omitted: bool,
 }
 
 impl HttpResponse {
@@ -22,24 +74,94 @@ impl HttpResponse {
         self.version = version.to_string();
     }
 
-    /// Setter for the code
-    pub fn set_code(&mut self, code: usize) {
+    /// Setter for the code, the message becomes its reason phrase
+    pub fn set_code(&mut self, code: StatusCode) {
         self.code = code;
+        self.message = code.get_reason_phrase().to_string();
     }
 
-    /// Setter for the message
+    /// Setter for the message, to use a reason phrase other than the default one
     pub fn set_message(&mut self, message: &str) {
         self.message = message.to_string()
     }
 
-    /// Adds a key and a value as a header
+    /// Getter for the code
+    pub fn get_code(&self) -> StatusCode {
+        self.code
+    }
+
+    /// Getter for the message
+    pub fn get_message(&self) -> &String {
+        &self.message
+    }
+
+    /// Adds a key and a value as a header, replacing the values it had
     pub fn add_header(&mut self, key: &str, value: &str) {
-        self.headers.insert(key.to_string(), value.to_string());
+        self.headers.insert(key, value);
+    }
+
+    /// Adds a value to a header, keeping the values it had, like for Set-Cookie
+    pub fn append_header(&mut self, key: &str, value: &str) {
+        self.headers.append(key, value);
+    }
+
+    /// Returns the first value of a header, ignoring the case of its name
+    pub fn get_header(&self, key: &str) -> Option<&str> {
+        self.headers.get(key)
+    }
+
+    /// Removes a header, ignoring the case of its name
+    pub fn remove_header(&mut self, key: &str) {
+        self.headers.remove(key);
+    }
+
+    /// Getter for the headers
+    pub fn get_headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the headers, to change them
+    pub fn get_headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
     }
 
     /// Concatenates a string to the body
     pub fn add_body(&mut self, string: &str) {
-        self.body.push_str(string);
+        self.body.extend_from_slice(string.as_bytes());
+    }
+
+    /// Concatenates raw bytes to the body
+    pub fn add_body_bytes(&mut self, bytes: &[u8]) {
+        self.body.extend_from_slice(bytes);
+    }
+
+    /// Replaces the body with raw bytes
+    pub fn set_body_bytes(&mut self, bytes: Vec<u8>) {
+        self.body = bytes;
+        self.body_reader = None;
+    }
+
+    /// Getter for the body
+    pub fn get_body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Leaves the body out when the response is sent, the headers stay the same
+    /// A response to HEAD keeps the Content-Length the body would have
+    pub fn omit_body(&mut self) {
+        self.body_omitted = true;
+    }
+
+    /// Returns true if the body is read while the response is sent
+    pub fn has_body_reader(&self) -> bool {
+        self.body_reader.is_some()
+    }
+
+    /// Sets a body that is read while the response is sent
+    /// If the length is unknown, the body is sent with the chunked transfer coding
+    pub fn set_body_reader(&mut self, reader: Box<dyn Read + Send>, length: Option<u64>) {
+        self.body.clear();
+        self.body_reader = Some(BodyReader { reader, length });
     }
 
     /// HttpResponse constructor
@@ -47,36 +169,193 @@ impl HttpResponse {
     pub fn new() -> Self {
         Self {
             version: "".to_string(),
-            code: 200,
-            message: "OK".to_string(),
-            headers: HashMap::new(),
-            body: "".to_string(),
+            code: StatusCode::OK,
+            message: StatusCode::OK.get_reason_phrase().to_string(),
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            body_reader: None,
+            body_omitted: false,
         }
     }
 
-    /// Returns a string version of the http response to send to the server
-    pub fn to_string(&self) -> String {
-        let mut string = "".to_string();
+    /// Returns an empty response with a status code
+    pub fn with_code(code: StatusCode) -> Self {
+        let mut http_response = Self::new();
+
+        http_response.set_code(code);
 
-        string.push_str(&self.version);
-        string.push(' ');
-        string.push_str(&self.code.to_string());
-        string.push(' ');
-        string.push_str(&self.message);
-        string.push('\n');
+        http_response
+    }
 
-        for (key, value) in &self.headers {
-            string.push_str(key);
-            string.push_str(": ");
-            string.push_str(value);
-            string.push('\n');
+    /// Returns an empty 404 Not Found response
+    pub fn not_found() -> Self {
+        Self::with_code(StatusCode::NOT_FOUND)
+    }
+
+    /// Returns a response that redirects the client to a location
+    pub fn redirect(location: &str, kind: RedirectKind) -> Self {
+        let mut http_response = Self::with_code(kind.get_code());
+
+        http_response.add_header("Location", location);
+
+        http_response
+    }
+
+    /// Writes the status line, the headers and the raw body to a stream
+    /// The framing headers (Content-Length or Transfer-Encoding) and the Date are set here
+    pub fn write_to<W: Write>(&mut self, writer: &mut W) -> Result<(), Error> {
+        if self.version.is_empty() {
+            self.version = DEFAULT_VERSION.to_string();
+        }
+
+        if self.get_header("Date").is_none() {
+            self.add_header("Date", &httpdate::fmt_http_date(SystemTime::now()));
+        }
+
+        self.remove_header("Content-Length");
+        self.remove_header("Transfer-Encoding");
+
+        let mut body_reader = self.body_reader.take();
+
+        if !self.code.allows_body() {
+            body_reader = None;
+            self.body.clear();
+        }
+
+        let chunked = match &body_reader {
+            _ if !self.code.allows_body() => false,
+            Some(BodyReader {
+                length: Some(length),
+                ..
+            }) => {
+                self.add_header("Content-Length", &length.to_string());
+                false
+            }
+            Some(BodyReader { length: None, .. }) if self.version == "HTTP/1.0" => {
+                // Without chunks, an HTTP/1.0 client knows the body ended when the connection closes
+                self.add_header("Connection", "close");
+                false
+            }
+            Some(BodyReader { length: None, .. }) => {
+                self.add_header("Transfer-Encoding", "chunked");
+                true
+            }
+            None => {
+                self.add_header("Content-Length", &self.body.len().to_string());
+                false
+            }
+        };
+
+        writer.write_all(&self.head())?;
+
+        if self.body_omitted {
+            body_reader = None;
+            self.body.clear();
         }
 
-        if !self.body.is_empty() {
-            string.push_str("\n");
-            string.push_str(&self.body);
+        match body_reader {
+            Some(BodyReader {
+                mut reader,
+                length: Some(length),
+            }) => {
+                std::io::copy(&mut (&mut reader).take(length), writer)?;
+            }
+            Some(BodyReader { mut reader, .. }) if chunked => {
+                Self::write_chunks(&mut reader, writer)?;
+            }
+            Some(BodyReader { mut reader, .. }) => {
+                std::io::copy(&mut reader, writer)?;
+            }
+            None => {
+                writer.write_all(&self.body)?;
+            }
         }
 
-        string
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Returns the status line and the headers, ended by an empty line
+    fn head(&self) -> Vec<u8> {
+        let mut head = "".to_string();
+
+        head.push_str(&self.version);
+        head.push(' ');
+        head.push_str(&self.code.get_code().to_string());
+        head.push(' ');
+        head.push_str(&self.message);
+        head.push_str("\r\n");
+
+        for (key, value) in self.headers.iter() {
+            head.push_str(key);
+            head.push_str(": ");
+            head.push_str(value);
+            head.push_str("\r\n");
+        }
+
+        head.push_str("\r\n");
+
+        head.into_bytes()
+    }
+
+    /// Writes a body with the chunked transfer coding
+    fn write_chunks<R: Read, W: Write>(reader: &mut R, writer: &mut W) -> Result<(), Error> {
+        let mut buffer = vec![0; CHUNK_SIZE];
+
+        loop {
+            let size = reader.read(&mut buffer)?;
+
+            if size == 0 {
+                break;
+            }
+
+            let mut chunk = format!("{:X}\r\n", size).into_bytes();
+
+            chunk.extend_from_slice(&buffer[..size]);
+            chunk.extend_from_slice(b"\r\n");
+
+            writer.write_all(&chunk)?;
+        }
+
+        writer.write_all(b"0\r\n\r\n")?;
+
+        Ok(())
+    }
+}
+
+impl Default for HttpResponse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_code_sets_the_reason_phrase() {
+        let http_response = HttpResponse::with_code(StatusCode::FORBIDDEN);
+
+        assert_eq!(http_response.get_code(), StatusCode::FORBIDDEN);
+        assert_eq!(http_response.get_message(), "Forbidden");
+    }
+
+    #[test]
+    fn not_found_is_an_empty_404() {
+        let http_response = HttpResponse::not_found();
+
+        assert_eq!(http_response.get_code(), StatusCode::NOT_FOUND);
+        assert_eq!(http_response.get_message(), "Not Found");
+        assert!(http_response.get_body().is_empty());
+    }
+
+    #[test]
+    fn redirect_sets_the_code_of_its_kind_and_the_location() {
+        let http_response = HttpResponse::redirect("/tasks", RedirectKind::SeeOther);
+
+        assert_eq!(http_response.get_code(), StatusCode::SEE_OTHER);
+        assert_eq!(http_response.get_header("Location"), Some("/tasks"));
     }
 }
@@ -0,0 +1,310 @@
+// Copyright 2023 Camilo Suárez Sandí
+
+use crate::http::http_error::HttpError;
+use crate::http::http_method::HttpMethod;
+use crate::http::http_query::percent_decode;
+use crate::http::http_request::HttpRequest;
+use crate::http::http_response::HttpResponse;
+use crate::http::http_status::StatusCode;
+
+/// A function that handles the requests of a route, it receives the app that owns the router
+pub type RouteHandler<A> =
+    fn(&A, &HttpRequest, &RouteParams, &mut HttpResponse) -> Result<(), HttpError>;
+
+/// The values of the parameters of a matched route, like the id in /tasks/:id
+pub struct RouteParams {
+    /// The names and the decoded values of the parameters
+    params: Vec<(String, String)>,
+}
+
+impl RouteParams {
+    /// Returns the value of a parameter
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A part of a route pattern, between two /
+enum RouteSegment {
+    /// A segment that must be equal to the path
+    Static(String),
+    /// A segment that matches any non empty value, like :id
+    Param(String),
+}
+
+/// A method, a pattern and the function that handles them
+struct Route<A> {
+    /// The http method
+    method: HttpMethod,
+    /// The segments of the pattern
+    segments: Vec<RouteSegment>,
+    /// The function that handles the requests
+    handler: RouteHandler<A>,
+}
+
+/// The result of looking for the route of a request
+pub enum RouteMatch<A> {
+    /// A route matched the method and the path
+    Found(RouteHandler<A>, RouteParams),
+    /// Some routes matched the path, but none the method, it has the allowed methods
+    MethodNotAllowed(Vec<HttpMethod>),
+    /// No route matched the path
+    NotFound,
+}
+
+impl<A> RouteMatch<A> {
+    /// Calls the handler of a found route, or answers 405 Method Not Allowed
+    /// Returns false if no route matched, so the request can be handled elsewhere
+    pub fn dispatch(
+        self,
+        app: &A,
+        http_request: &HttpRequest,
+        http_response: &mut HttpResponse,
+    ) -> Result<bool, HttpError> {
+        match self {
+            RouteMatch::Found(handler, params) => {
+                handler(app, http_request, &params, http_response)?;
+
+                Ok(true)
+            }
+            RouteMatch::MethodNotAllowed(methods) => {
+                let verbs: Vec<&str> = methods.iter().map(HttpMethod::get_verb).collect();
+
+                http_response.set_code(StatusCode::METHOD_NOT_ALLOWED);
+                http_response.add_header("Allow", &verbs.join(", "));
+
+                Ok(true)
+            }
+            RouteMatch::NotFound => Ok(false),
+        }
+    }
+}
+
+/// Dispatches requests to handlers by method and path pattern
+pub struct HttpRouter<A> {
+    /// The routes, in the order they were added
+    routes: Vec<Route<A>>,
+}
+
+impl<A> HttpRouter<A> {
+    /// HttpRouter constructor
+    /// Returns a router without routes
+    pub fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    /// Adds a route for a method and a pattern like /tasks/:id
+    pub fn add(&mut self, method: HttpMethod, pattern: &str, handler: RouteHandler<A>) {
+        let segments = Self::split(pattern)
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(name) => RouteSegment::Param(name.to_string()),
+                None => RouteSegment::Static(segment.to_string()),
+            })
+            .collect();
+
+        self.routes.push(Route {
+            method,
+            segments,
+            handler,
+        });
+    }
+
+    /// Adds a route for GET
+    pub fn get(&mut self, pattern: &str, handler: RouteHandler<A>) {
+        self.add(HttpMethod::Get, pattern, handler);
+    }
+
+    /// Adds a route for POST
+    pub fn post(&mut self, pattern: &str, handler: RouteHandler<A>) {
+        self.add(HttpMethod::Post, pattern, handler);
+    }
+
+    /// Adds a route for PUT
+    pub fn put(&mut self, pattern: &str, handler: RouteHandler<A>) {
+        self.add(HttpMethod::Put, pattern, handler);
+    }
+
+    /// Adds a route for DELETE
+    pub fn delete(&mut self, pattern: &str, handler: RouteHandler<A>) {
+        self.add(HttpMethod::Delete, pattern, handler);
+    }
+
+    /// Looks for the route of a request
+    /// HEAD uses the GET route of the path if it does not have its own
+    /// OPTIONS without a route of its own is not found, so the server can answer it
+    /// So is an extension method without routes, which the server answers with 501
+    pub fn find(&self, http_request: &HttpRequest) -> RouteMatch<A> {
+        let method = http_request.get_method();
+
+        let path = http_request.get_path();
+
+        if let Some(found) = self.find_route(method, path) {
+            return found;
+        }
+
+        if *method == HttpMethod::Head {
+            if let Some(found) = self.find_route(&HttpMethod::Get, path) {
+                return found;
+            }
+        }
+
+        let unknown = method.is_extension() && !self.has_method(method);
+
+        let allowed = self.get_allowed_methods(path);
+
+        if allowed.is_empty() || unknown || *method == HttpMethod::Options {
+            RouteMatch::NotFound
+        } else {
+            RouteMatch::MethodNotAllowed(allowed)
+        }
+    }
+
+    /// Returns the methods allowed for a path, or for all the routes if the path is *
+    /// HEAD is allowed with GET, and OPTIONS with any route
+    pub fn get_allowed_methods(&self, path: &str) -> Vec<HttpMethod> {
+        let mut allowed = Vec::new();
+
+        for route in &self.routes {
+            let matches = path == "*" || Self::match_path(&route.segments, path).is_some();
+
+            if matches && !allowed.contains(&route.method) {
+                allowed.push(route.method.clone());
+            }
+        }
+
+        if allowed.contains(&HttpMethod::Get) && !allowed.contains(&HttpMethod::Head) {
+            allowed.push(HttpMethod::Head);
+        }
+
+        if !allowed.is_empty() && !allowed.contains(&HttpMethod::Options) {
+            allowed.push(HttpMethod::Options);
+        }
+
+        allowed
+    }
+
+    /// Returns true if there is a route for a method, with any pattern
+    fn has_method(&self, method: &HttpMethod) -> bool {
+        self.routes.iter().any(|route| &route.method == method)
+    }
+
+    /// Returns the first route of a method that matches a path
+    fn find_route(&self, method: &HttpMethod, path: &str) -> Option<RouteMatch<A>> {
+        self.routes
+            .iter()
+            .filter(|route| &route.method == method)
+            .find_map(|route| {
+                Self::match_path(&route.segments, path)
+                    .map(|params| RouteMatch::Found(route.handler, params))
+            })
+    }
+
+    /// Returns the parameters if the path matches the segments of a pattern
+    fn match_path(segments: &[RouteSegment], path: &str) -> Option<RouteParams> {
+        let parts: Vec<&str> = Self::split(path).collect();
+
+        if parts.len() != segments.len() {
+            return None;
+        }
+
+        let mut params = Vec::new();
+
+        for (segment, part) in segments.iter().zip(parts) {
+            match segment {
+                RouteSegment::Static(value) if value == part => {}
+                RouteSegment::Param(name) if !part.is_empty() => {
+                    params.push((name.clone(), percent_decode(part)));
+                }
+                _ => return None,
+            }
+        }
+
+        Some(RouteParams { params })
+    }
+
+    /// Splits a path or a pattern in segments, / is a single empty segment
+    fn split(path: &str) -> impl Iterator<Item = &str> {
+        path.strip_prefix('/').unwrap_or(path).split('/')
+    }
+}
+
+impl<A> Default for HttpRouter<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::http::http_config::HttpConfig;
+
+    /// Returns a request with a method and a path
+    fn request(method: &str, path: &str) -> HttpRequest {
+        let raw = format!("{} {} HTTP/1.1\r\nHost: localhost\r\n\r\n", method, path);
+
+        HttpRequest::from_reader(&mut Cursor::new(raw), &HttpConfig::new())
+            .ok()
+            .flatten()
+            .unwrap()
+    }
+
+    fn handle(
+        _: &(),
+        _: &HttpRequest,
+        _: &RouteParams,
+        _: &mut HttpResponse,
+    ) -> Result<(), HttpError> {
+        Ok(())
+    }
+
+    fn router() -> HttpRouter<()> {
+        let mut router = HttpRouter::new();
+
+        router.get("/", handle);
+        router.add(HttpMethod::new("LOCK").unwrap(), "/files/:name", handle);
+
+        router
+    }
+
+    #[test]
+    fn head_uses_the_get_route() {
+        assert!(matches!(
+            router().find(&request("HEAD", "/")),
+            RouteMatch::Found(..)
+        ));
+    }
+
+    #[test]
+    fn other_method_on_a_route_is_not_allowed() {
+        assert!(matches!(
+            router().find(&request("POST", "/")),
+            RouteMatch::MethodNotAllowed(_)
+        ));
+    }
+
+    #[test]
+    fn extension_method_without_routes_is_not_found() {
+        assert!(matches!(
+            router().find(&request("PROPFIND", "/")),
+            RouteMatch::NotFound
+        ));
+    }
+
+    #[test]
+    fn extension_method_with_routes_is_not_allowed_elsewhere() {
+        assert!(matches!(
+            router().find(&request("LOCK", "/files/a.txt")),
+            RouteMatch::Found(..)
+        ));
+        assert!(matches!(
+            router().find(&request("LOCK", "/")),
+            RouteMatch::MethodNotAllowed(_)
+        ));
+    }
+}
@@ -1,20 +1,51 @@
 // Copyright 2023 Camilo Suárez Sandí
 
 use std::fs;
+use std::io::BufRead;
+use std::io::BufReader;
 use std::io::Error;
-use std::io::Write;
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::net::SocketAddr;
 use std::net::TcpListener;
 use std::net::TcpStream;
+use std::panic;
+use std::panic::AssertUnwindSafe;
 use std::path;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::sync::Mutex;
-use std::sync::MutexGuard;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use signal_hook::consts::SIGINT;
+use signal_hook::consts::SIGTERM;
+use signal_hook::iterator::Signals;
 
 use crate::http::http_app::HttpApp;
+use crate::http::http_compression::compress_response;
+use crate::http::http_config::HttpConfig;
+use crate::http::http_config::OverflowPolicy;
+use crate::http::http_deadline::DeadlineReader;
+use crate::http::http_error::HttpError;
+use crate::http::http_method::HttpMethod;
 use crate::http::http_request::HttpRequest;
 use crate::http::http_response::HttpResponse;
+use crate::http::http_static::HttpStatic;
+use crate::http::http_status::StatusCode;
+use crate::thread::thread_pool::panic_message;
 use crate::thread::thread_pool::ThreadPool;
 
+/// The time to write a 503 response when the queue is full
+const UNAVAILABLE_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The pause after a failed accept, so an error that repeats does not keep a core busy
+const ACCEPT_ERROR_DELAY: Duration = Duration::from_millis(10);
+
 /// An http server
 pub struct HttpServer {
     /// The address of the server
@@ -24,7 +55,58 @@ pub struct HttpServer {
     /// A TcpListener
     listener: TcpListener,
     /// All the apps connected to the server
-    apps: Mutex<Vec<Box<dyn HttpApp + Send + Sync>>>,
+    apps: Vec<Box<dyn HttpApp>>,
+    /// The settings of the server
+    config: HttpConfig,
+    /// Set when the server has to stop accepting connections
+    shutdown: Arc<AtomicBool>,
+    /// The number of connections waiting for a worker
+    queue_depth: Arc<AtomicUsize>,
+}
+
+/// A handle to stop a running server from another thread
+#[derive(Clone)]
+pub struct ServerHandle {
+    /// The address the server listens to
+    address: SocketAddr,
+    /// Shared with the server
+    shutdown: Arc<AtomicBool>,
+    /// Shared with the thread pool of the server
+    queue_depth: Arc<AtomicUsize>,
+}
+
+impl ServerHandle {
+    /// Asks the server to stop accepting connections and finish the ones in progress
+    /// HttpServer::start returns once the work is done, or the shutdown timeout expires
+    pub fn shutdown(&self) {
+        if self.shutdown.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let mut address = self.address;
+
+        if address.ip().is_unspecified() {
+            let ip = match address.ip() {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            };
+
+            address.set_ip(ip);
+        }
+
+        // The server is blocked waiting for a connection, this one wakes it up
+        let _ = TcpStream::connect(address);
+    }
+
+    /// Returns true if the server was asked to stop
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of connections waiting for a worker
+    pub fn get_queue_depth(&self) -> usize {
+        self.queue_depth.load(Ordering::SeqCst)
+    }
 }
 
 impl HttpServer {
@@ -37,102 +119,478 @@ impl HttpServer {
             address: address.to_string(),
             port: port.to_string(),
             listener,
-            apps: Mutex::new(Vec::new()),
+            apps: Vec::new(),
+            config: HttpConfig::new(),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            queue_depth: Arc::new(AtomicUsize::new(0)),
         };
 
-        return Ok(server);
+        Ok(server)
+    }
+
+    /// Getter for the settings
+    pub fn get_config(&self) -> &HttpConfig {
+        &self.config
+    }
+
+    /// Setter for the settings
+    pub fn set_config(&mut self, config: HttpConfig) {
+        self.config = config;
     }
 
-    pub fn add_app(&mut self, app: Box<dyn HttpApp + Send + Sync>) {
-        self.apps.lock().unwrap().push(app);
+    pub fn add_app(&mut self, app: Box<dyn HttpApp>) {
+        self.apps.push(app);
+    }
+
+    /// Returns a handle to stop the server
+    pub fn get_handle(&self) -> Result<ServerHandle, Error> {
+        Ok(ServerHandle {
+            address: self.listener.local_addr()?,
+            shutdown: Arc::clone(&self.shutdown),
+            queue_depth: Arc::clone(&self.queue_depth),
+        })
+    }
+
+    /// Stops the server gracefully when the process receives SIGINT or SIGTERM
+    /// A second signal stops the process right away
+    pub fn shutdown_on_signals(&self) -> Result<(), Error> {
+        let handle = self.get_handle()?;
+
+        let mut signals = Signals::new([SIGINT, SIGTERM])?;
+
+        thread::spawn(move || {
+            for _ in signals.forever() {
+                if handle.is_shutdown() {
+                    std::process::exit(1);
+                }
+
+                println!("Stopping the server");
+
+                handle.shutdown();
+            }
+        });
+
+        Ok(())
     }
 
     /// Starts listening to client requests and sends the server responses
     pub fn start(&mut self) -> Result<(), Error> {
         println!("Server running at {}:{}", self.address, self.port);
 
-        let workers_count = std::thread::available_parallelism()?.get();
+        let workers_count = match self.config.get_workers_count() {
+            Some(workers_count) => workers_count,
+            None => std::thread::available_parallelism()?.get(),
+        };
+
+        let mut thread_pool = ThreadPool::with_queue_depth(
+            workers_count,
+            self.config.get_queue_capacity(),
+            Arc::clone(&self.queue_depth),
+        );
 
-        let thread_pool = ThreadPool::new(workers_count);
+        for app in self.apps.iter_mut() {
+            app.configure(&self.config);
+        }
 
-        let mut apps = Mutex::new(vec![]);
+        let apps = Arc::new(std::mem::take(&mut self.apps));
 
-        std::mem::swap(&mut apps, &mut self.apps);
+        let config = Arc::new(self.config.clone());
 
-        let apps = Arc::new(apps);
+        let public = Arc::new(HttpStatic::new(&config));
 
         for stream in self.listener.incoming() {
+            if self.shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+
             let apps = Arc::clone(&apps);
 
-            let mut stream = stream?;
+            let config = Arc::clone(&config);
+
+            let public = Arc::clone(&public);
+
+            let shutdown = Arc::clone(&self.shutdown);
+
+            // A failed accept, like too many open files, only loses that connection
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(error) => {
+                    eprintln!("Accept error: {}", error);
+
+                    thread::sleep(ACCEPT_ERROR_DELAY);
+
+                    continue;
+                }
+            };
 
-            thread_pool.execute(move || {
-                let apps = apps.lock().unwrap();
+            // The stream is shared with the work, so a rejected connection can still be answered
+            let stream = Arc::new(stream);
 
-                HttpServer::handle_connection(&mut stream, apps).unwrap();
-            })
+            let connection = Arc::clone(&stream);
+
+            let work = move || {
+                if let Err(error) =
+                    HttpServer::handle_connection(&connection, &apps, &config, &public, &shutdown)
+                {
+                    eprintln!("Connection error: {}", error);
+                }
+            };
+
+            match self.config.get_overflow_policy() {
+                OverflowPolicy::Block => thread_pool.execute(work),
+                OverflowPolicy::Reject => {
+                    if !thread_pool.try_execute(work) {
+                        HttpServer::serve_unavailable(&stream);
+                    }
+                }
+                OverflowPolicy::Drop => {
+                    // The connection closes when the stream is dropped
+                    thread_pool.try_execute(work);
+                }
+            }
+        }
+
+        if !thread_pool.shutdown(self.config.get_shutdown_timeout()) {
+            println!("Some connections were still open when the server stopped");
         }
 
+        println!("Server stopped");
+
         Ok(())
     }
 
-    /// Handles a single request and sends a single response
+    /// Handles the requests of a connection and sends their responses
+    /// The connection is kept open while the client wants it, up to the configured limits
     fn handle_connection(
-        stream: &mut TcpStream,
-        mut apps: MutexGuard<Vec<Box<dyn HttpApp + Send + Sync>>>,
+        stream: &TcpStream,
+        apps: &[Box<dyn HttpApp>],
+        config: &HttpConfig,
+        public: &HttpStatic,
+        shutdown: &AtomicBool,
     ) -> Result<(), Error> {
-        let http_request = HttpRequest::from_stream(&stream)?;
+        stream.set_write_timeout(Some(config.get_write_timeout()))?;
 
-        let mut http_response = HttpResponse::new();
+        let mut reader =
+            BufReader::new(DeadlineReader::new(stream, config.get_keep_alive_timeout()));
 
-        http_response.set_version(http_request.get_version());
+        let mut writer = stream;
 
-        for app in apps.iter_mut() {
-            if app.handle(&http_request, &mut http_response)? {
-                stream.write_all(http_response.to_string().as_bytes())?;
-                return Ok(());
+        for count in 1..=config.get_max_requests_per_connection() {
+            // The connection is idle until the first byte of the next request
+            reader
+                .get_mut()
+                .set_timeout(config.get_keep_alive_timeout());
+            reader.get_mut().set_deadline(None);
+
+            match reader.fill_buf() {
+                Ok([]) => return Ok(()),
+                Ok(_) => {}
+                Err(error) if HttpServer::is_idle_error(&error) => return Ok(()),
+                Err(error) => return Err(error),
+            }
+
+            let http_request = match HttpServer::read_request(&mut reader, config) {
+                Ok(Some(http_request)) => http_request,
+                Ok(None) => return Ok(()),
+                Err(error) if HttpServer::is_closed_error(&error) => return Ok(()),
+                Err(error) => {
+                    // The rest of the connection can not be trusted after a bad request
+                    let mut http_response = HttpResponse::new();
+
+                    HttpServer::serve_error(apps, &mut http_response, &error)?;
+                    http_response.add_header("Connection", "close");
+                    http_response.write_to(&mut writer)?;
+
+                    return Ok(());
+                }
+            };
+
+            let mut http_response = HttpResponse::new();
+
+            http_response.set_version(http_request.get_version());
+
+            HttpServer::handle_request(&http_request, &mut http_response, apps, public)?;
+
+            compress_response(&http_request, &mut http_response, config)?;
+
+            let keep_alive = count < config.get_max_requests_per_connection()
+                && !shutdown.load(Ordering::SeqCst)
+                && http_request.is_keep_alive()
+                && http_response.get_header("Connection") != Some("close");
+
+            if !keep_alive {
+                http_response.add_header("Connection", "close");
+            } else if http_request.get_version() != "HTTP/1.1" {
+                http_response.add_header("Connection", "keep-alive");
+            }
+
+            if *http_request.get_method() == HttpMethod::Head {
+                http_response.omit_body();
+            }
+
+            http_response.write_to(&mut writer)?;
+
+            // The response can decide to close the connection while it is written
+            if !keep_alive || http_response.get_header("Connection") == Some("close") {
+                break;
             }
         }
 
-        if HttpServer::serve_public(&http_request, &mut http_response)? {
-            stream.write_all(http_response.to_string().as_bytes())?;
-            return Ok(());
+        Ok(())
+    }
+
+    /// Reads a request that already started to arrive
+    /// The head has to arrive before the header timeout, a read that waits too long is 408
+    fn read_request(
+        reader: &mut BufReader<DeadlineReader>,
+        config: &HttpConfig,
+    ) -> Result<Option<HttpRequest>, HttpError> {
+        reader.get_mut().set_timeout(config.get_read_timeout());
+        reader
+            .get_mut()
+            .set_deadline(Some(Instant::now() + config.get_header_timeout()));
+
+        let result = match HttpRequest::head_from_reader(reader, config) {
+            Ok(Some(mut http_request)) => {
+                reader.get_mut().set_deadline(None);
+
+                http_request
+                    .read_body(reader, config)
+                    .map(|_| Some(http_request))
+            }
+            result => result,
+        };
+
+        result.map_err(|error| {
+            let timed_out = error
+                .get_io_error()
+                .is_some_and(HttpServer::is_timeout_error);
+
+            if timed_out {
+                HttpError::new(StatusCode::REQUEST_TIMEOUT, "The request took too long")
+            } else {
+                error
+            }
+        })
+    }
+
+    /// Handles a single request, filling the response
+    /// The static files are served when no app handles the request
+    /// Errors and panics of the apps are answered with a 500 page
+    fn handle_request(
+        http_request: &HttpRequest,
+        http_response: &mut HttpResponse,
+        apps: &[Box<dyn HttpApp>],
+        public: &HttpStatic,
+    ) -> Result<(), Error> {
+        for app in apps {
+            // A panicking app answers 500 instead of taking the worker down
+            let handled =
+                panic::catch_unwind(AssertUnwindSafe(|| app.handle(http_request, http_response)));
+
+            match handled {
+                Ok(Ok(true)) => return Ok(()),
+                Ok(Ok(false)) => {}
+                Ok(Err(error)) => {
+                    return HttpServer::serve_failure(http_request, http_response, apps, &error);
+                }
+                Err(payload) => {
+                    let error = HttpError::internal(panic_message(payload.as_ref()));
+
+                    return HttpServer::serve_failure(http_request, http_response, apps, &error);
+                }
+            }
+        }
+
+        // A method the server does not know can only be answered by an app, and so can a tunnel
+        let method = http_request.get_method();
+
+        if method.is_extension() || *method == HttpMethod::Connect {
+            let code = StatusCode::NOT_IMPLEMENTED;
+
+            let error = HttpError::new(code, code.get_reason_phrase());
+
+            return HttpServer::serve_error(apps, http_response, &error);
+        }
+
+        if *http_request.get_method() == HttpMethod::Options {
+            return HttpServer::serve_options(http_request, http_response, apps, public);
         }
 
-        HttpServer::serve_not_found(&mut http_response)?;
-        stream.write_all(http_response.to_string().as_bytes())?;
+        match public.serve(http_request, http_response) {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                let error = HttpError::not_found(StatusCode::NOT_FOUND.get_reason_phrase());
+
+                HttpServer::serve_error(apps, http_response, &error)
+            }
+            Err(error) => HttpServer::serve_failure(http_request, http_response, apps, &error),
+        }
+    }
+
+    /// Answers OPTIONS with the methods the apps and the static files allow for the path
+    /// OPTIONS * asks for the methods of the whole server
+    fn serve_options(
+        http_request: &HttpRequest,
+        http_response: &mut HttpResponse,
+        apps: &[Box<dyn HttpApp>],
+        public: &HttpStatic,
+    ) -> Result<(), Error> {
+        let path = http_request.get_path();
+
+        let mut allowed = public.get_allowed_methods(path);
+
+        if path == "*" {
+            allowed.push(HttpMethod::Get);
+            allowed.push(HttpMethod::Head);
+        }
+
+        for app in apps {
+            let methods = panic::catch_unwind(AssertUnwindSafe(|| app.get_allowed_methods(path)))
+                .unwrap_or_default();
+
+            for method in methods {
+                if !allowed.contains(&method) {
+                    allowed.push(method);
+                }
+            }
+        }
+
+        if allowed.is_empty() {
+            let error = HttpError::not_found(StatusCode::NOT_FOUND.get_reason_phrase());
+
+            return HttpServer::serve_error(apps, http_response, &error);
+        }
+
+        if !allowed.contains(&HttpMethod::Options) {
+            allowed.push(HttpMethod::Options);
+        }
+
+        let verbs: Vec<&str> = allowed.iter().map(HttpMethod::get_verb).collect();
+
+        http_response.add_header("Allow", &verbs.join(", "));
 
         Ok(())
     }
 
-    fn serve_public(
+    /// Returns true if the error means the client stopped sending, or closed the connection
+    fn is_idle_error(error: &Error) -> bool {
+        HttpServer::is_timeout_error(error)
+            || matches!(
+                error.kind(),
+                ErrorKind::UnexpectedEof
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+            )
+    }
+
+    /// Returns true if the error means the client closed the connection in the middle of a request
+    fn is_closed_error(error: &HttpError) -> bool {
+        error.get_io_error().is_some_and(|error| {
+            !HttpServer::is_timeout_error(error) && HttpServer::is_idle_error(error)
+        })
+    }
+
+    /// Returns true if the error means a read or a write waited too long
+    fn is_timeout_error(error: &Error) -> bool {
+        matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+    }
+
+    /// Replaces whatever the response had with the page of an error
+    /// Errors of the server are logged, since their cause is not shown to the client
+    fn serve_failure(
         http_request: &HttpRequest,
         http_response: &mut HttpResponse,
-    ) -> Result<bool, Error> {
-        let mut path = "./public".to_string();
+        apps: &[Box<dyn HttpApp>],
+        http_error: &HttpError,
+    ) -> Result<(), Error> {
+        if http_error.get_code().is_server_error() {
+            eprintln!(
+                "{} {}: {}",
+                http_request.get_method().get_verb(),
+                http_request.get_target(),
+                http_error
+            );
+        }
 
-        path.push_str(http_request.get_target());
+        *http_response = HttpResponse::new();
 
-        let file = path::Path::new(&path);
+        http_response.set_version(http_request.get_version());
 
-        if let Ok(data) = fs::read_to_string(file) {
-            http_response.add_body(&data);
+        HttpServer::serve_error(apps, http_response, http_error)
+    }
 
-            return Ok(true);
+    /// Fills the response of an error
+    /// The apps can serve their own page, otherwise one from ./pages is used
+    fn serve_error(
+        apps: &[Box<dyn HttpApp>],
+        http_response: &mut HttpResponse,
+        http_error: &HttpError,
+    ) -> Result<(), Error> {
+        let code = http_error.get_code();
+
+        http_response.set_code(code);
+
+        for app in apps {
+            let served = panic::catch_unwind(AssertUnwindSafe(|| {
+                app.serve_error(http_error, http_response)
+            }));
+
+            match served {
+                Ok(Ok(true)) => return Ok(()),
+                Ok(Ok(false)) => {}
+                Ok(Err(error)) => eprintln!("An app failed to serve an error page: {}", error),
+                Err(payload) => eprintln!(
+                    "An app panicked serving an error page: {}",
+                    panic_message(payload.as_ref())
+                ),
+            }
         }
 
-        Ok(false)
+        let page = match code {
+            StatusCode::NOT_FOUND => "./pages/not_found.html",
+            StatusCode::INTERNAL_SERVER_ERROR => "./pages/internal_error.html",
+            _ => "./pages/error.html",
+        };
+
+        HttpServer::serve_error_page(http_response, http_error, page)
     }
 
-    fn serve_not_found(http_response: &mut HttpResponse) -> Result<(), Error> {
-        let file = path::Path::new("./pages/not_found.html");
+    /// Serves the page of an error, with the code and the public message of the error
+    fn serve_error_page(
+        http_response: &mut HttpResponse,
+        http_error: &HttpError,
+        page: &str,
+    ) -> Result<(), Error> {
+        let file = path::Path::new(page);
 
         let data = fs::read_to_string(file)?;
 
-        http_response.set_code(404);
-        http_response.set_message("Not Found");
+        let mut context = tera::Context::new();
+
+        context.insert("code", &http_response.get_code().get_code());
+        context.insert("message", http_response.get_message());
+        context.insert("detail", http_error.get_message());
+
+        let data = tera::Tera::one_off(&data, &context, true)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+
+        http_response.add_header("Content-Type", "text/html; charset=utf-8");
         http_response.add_body(&data);
 
         Ok(())
     }
+
+    /// Answers a connection that could not be queued, from the thread that accepts them
+    fn serve_unavailable(mut stream: &TcpStream) {
+        let mut http_response = HttpResponse::new();
+
+        http_response.set_code(StatusCode::SERVICE_UNAVAILABLE);
+        http_response.add_header("Retry-After", "1");
+        http_response.add_header("Connection", "close");
+
+        // A slow client must not stop the server from accepting connections
+        let _ = stream.set_write_timeout(Some(UNAVAILABLE_WRITE_TIMEOUT));
+        let _ = http_response.write_to(&mut stream);
+    }
 }
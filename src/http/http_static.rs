@@ -0,0 +1,661 @@
+// Copyright 2023 Camilo Suárez Sandí
+
+use std::fs;
+use std::fs::Metadata;
+use std::io::Cursor;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use serde::Serialize;
+
+use crate::http::http_compression::add_vary;
+use crate::http::http_compression::ContentCoding;
+use crate::http::http_config::HttpConfig;
+use crate::http::http_error::HttpError;
+use crate::http::http_method::HttpMethod;
+use crate::http::http_mime::MimeTypes;
+use crate::http::http_query::percent_decode;
+use crate::http::http_query::percent_encode;
+use crate::http::http_range::parse_ranges;
+use crate::http::http_range::ByteRange;
+use crate::http::http_request::HttpRequest;
+use crate::http::http_response::HttpResponse;
+use crate::http::http_status::StatusCode;
+
+/// The file served when a directory is requested
+pub const INDEX_FILE: &str = "index.html";
+
+/// A file or a directory of a directory listing
+#[derive(Serialize)]
+struct DirectoryEntry {
+    /// The name of the file
+    name: String,
+    /// True if it is a directory
+    directory: bool,
+    /// The size of the file in bytes, directories do not have one
+    size: Option<u64>,
+    /// The last modification, as an http date
+    modified: Option<String>,
+}
+
+/// Serves the files of a directory, like ./public
+pub struct HttpStatic {
+    /// The directory with the files
+    root: PathBuf,
+    /// The media types of the files
+    mime_types: MimeTypes,
+    /// The Cache-Control values, by path prefix
+    cache_controls: Vec<(String, String)>,
+    /// If the .gz siblings of the files are served to the clients that accept gzip
+    precompressed: bool,
+    /// If the directories without an index.html are listed
+    directory_listing: bool,
+}
+
+impl HttpStatic {
+    /// HttpStatic constructor
+    /// Returns a handler for the files inside the public root of the settings
+    pub fn new(config: &HttpConfig) -> Self {
+        Self {
+            root: PathBuf::from(config.get_public_root()),
+            mime_types: config.get_mime_types().clone(),
+            cache_controls: config.get_cache_controls().to_vec(),
+            precompressed: config.get_precompressed(),
+            directory_listing: config.get_directory_listing(),
+        }
+    }
+
+    /// Getter for the root
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Serves the file of the request path
+    /// Returns false if there is no file to serve, so the request can be handled elsewhere
+    pub fn serve(
+        &self,
+        http_request: &HttpRequest,
+        http_response: &mut HttpResponse,
+    ) -> Result<bool, HttpError> {
+        let mut file = match self.resolve(http_request.get_path()) {
+            Some(file) => file,
+            None => return Ok(false),
+        };
+
+        let mut metadata = match fs::metadata(&file) {
+            Ok(metadata) if metadata.is_file() || metadata.is_dir() => metadata,
+            _ => return Ok(false),
+        };
+
+        if !matches!(
+            http_request.get_method(),
+            HttpMethod::Get | HttpMethod::Head
+        ) {
+            http_response.set_code(StatusCode::METHOD_NOT_ALLOWED);
+            http_response.add_header("Allow", "GET, HEAD, OPTIONS");
+
+            return Ok(true);
+        }
+
+        if metadata.is_dir() {
+            let path = http_request.get_path();
+
+            // The relative links of the index must start from inside the directory
+            if !path.ends_with('/') {
+                Self::redirect_to_directory(http_request, http_response);
+
+                return Ok(true);
+            }
+
+            let index = file.join(INDEX_FILE);
+
+            match fs::metadata(&index) {
+                Ok(index_metadata) if index_metadata.is_file() && self.is_inside_root(&index) => {
+                    file = index;
+                    metadata = index_metadata;
+                }
+                _ if self.directory_listing => {
+                    self.serve_listing(http_request, http_response, &file)?;
+
+                    return Ok(true);
+                }
+                _ => return Ok(false),
+            }
+        }
+
+        // The type is the one of the original file, even when its .gz sibling is sent
+        let content_type = self.mime_types.content_type(&file);
+
+        let (file, metadata, coding) =
+            match self.find_precompressed(http_request, http_response, &file) {
+                Some((compressed, metadata)) => (compressed, metadata, Some(ContentCoding::Gzip)),
+                None => (file, metadata, None),
+            };
+
+        let modified = Self::get_modified(&metadata);
+
+        let mut etag = Self::get_etag(&metadata, modified);
+
+        if let Some(coding) = coding {
+            // Each representation of the file has its own tag
+            etag = format!("{}-{}\"", etag.trim_end_matches('"'), coding.get_name());
+
+            http_response.add_header("Content-Encoding", coding.get_name());
+        }
+
+        http_response.add_header("ETag", &etag);
+
+        if let Some(modified) = modified {
+            http_response.add_header("Last-Modified", &httpdate::fmt_http_date(modified));
+        }
+
+        if let Some(cache_control) = self.get_cache_control(http_request.get_path()) {
+            http_response.add_header("Cache-Control", cache_control);
+        }
+
+        if Self::is_not_modified(http_request, &etag, modified) {
+            http_response.set_code(StatusCode::NOT_MODIFIED);
+
+            return Ok(true);
+        }
+
+        let length = metadata.len();
+
+        http_response.add_header("Accept-Ranges", "bytes");
+
+        match Self::get_ranges(http_request, &etag, modified, length) {
+            None => {
+                let data = fs::File::open(&file)?;
+
+                http_response.add_header("Content-Type", &content_type);
+                http_response.set_body_reader(Box::new(data), Some(length));
+            }
+            Some(ranges) if ranges.is_empty() => {
+                http_response.set_code(StatusCode::RANGE_NOT_SATISFIABLE);
+                http_response.add_header("Content-Range", &format!("bytes */{}", length));
+            }
+            Some(ranges) if ranges.len() == 1 => {
+                let range = ranges[0];
+
+                http_response.set_code(StatusCode::PARTIAL_CONTENT);
+                http_response.add_header("Content-Type", &content_type);
+                http_response.add_header("Content-Range", &range.content_range(length));
+                http_response
+                    .set_body_reader(Self::open_range(&file, range)?, Some(range.get_length()));
+            }
+            Some(ranges) => {
+                Self::serve_ranges(http_response, &file, &content_type, &etag, &ranges, length)?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Redirects a directory without a trailing slash, like /docs, to /docs/
+    /// The location is built from the normalized path, so //host/docs can not lead to another host
+    fn redirect_to_directory(http_request: &HttpRequest, http_response: &mut HttpResponse) {
+        let segments = Self::get_segments(http_request.get_path()).unwrap_or_default();
+
+        let mut location = String::from("/");
+
+        for segment in segments {
+            location.push_str(&percent_encode(&segment));
+            location.push('/');
+        }
+
+        let query = http_request.get_query_string();
+
+        if !query.is_empty() {
+            location.push('?');
+            location.push_str(query);
+        }
+
+        http_response.set_code(StatusCode::MOVED_PERMANENTLY);
+        http_response.add_header("Location", &location);
+    }
+
+    /// Serves the list of the files of a directory
+    /// It is sent as json if the client accepts it, otherwise as an html page
+    fn serve_listing(
+        &self,
+        http_request: &HttpRequest,
+        http_response: &mut HttpResponse,
+        directory: &Path,
+    ) -> Result<(), HttpError> {
+        let entries = self.read_directory(directory)?;
+
+        let json = http_request
+            .get_header("Accept")
+            .is_some_and(|accept| accept.contains("application/json"));
+
+        if json {
+            let data = serde_json::to_string(&entries).map_err(HttpError::internal)?;
+
+            http_response.add_header("Content-Type", "application/json");
+            http_response.add_body(&data);
+
+            return Ok(());
+        }
+
+        let file = Path::new("./pages/directory.html");
+
+        let data = fs::read_to_string(file)?;
+
+        let mut context = tera::Context::new();
+
+        context.insert("path", &percent_decode(http_request.get_path()));
+        context.insert("entries", &entries);
+
+        let data = tera::Tera::one_off(&data, &context, true).map_err(HttpError::internal)?;
+
+        http_response.add_header("Content-Type", "text/html; charset=utf-8");
+        http_response.add_body(&data);
+
+        Ok(())
+    }
+
+    /// Returns the visible entries of a directory, the directories first and then by name
+    fn read_directory(&self, directory: &Path) -> Result<Vec<DirectoryEntry>, HttpError> {
+        let mut entries = Vec::new();
+
+        for entry in fs::read_dir(directory)? {
+            let entry = entry?;
+
+            // Hidden files can not be served, so they are not listed either
+            let name = match entry.file_name().into_string() {
+                Ok(name) if !name.starts_with('.') => name,
+                _ => continue,
+            };
+
+            let path = entry.path();
+
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) if self.is_inside_root(&path) => metadata,
+                _ => continue,
+            };
+
+            let directory = metadata.is_dir();
+
+            entries.push(DirectoryEntry {
+                name,
+                directory,
+                size: (!directory).then_some(metadata.len()),
+                modified: metadata.modified().ok().map(httpdate::fmt_http_date),
+            });
+        }
+
+        entries.sort_by(|a, b| b.directory.cmp(&a.directory).then(a.name.cmp(&b.name)));
+
+        Ok(entries)
+    }
+
+    /// Returns the .gz sibling of a file, if it exists and the client accepts gzip
+    fn find_precompressed(
+        &self,
+        http_request: &HttpRequest,
+        http_response: &mut HttpResponse,
+        file: &Path,
+    ) -> Option<(PathBuf, Metadata)> {
+        if !self.precompressed {
+            return None;
+        }
+
+        let mut compressed = file.as_os_str().to_owned();
+
+        compressed.push(".gz");
+
+        let compressed = PathBuf::from(compressed);
+
+        let metadata = match fs::metadata(&compressed) {
+            Ok(metadata) if metadata.is_file() && self.is_inside_root(&compressed) => metadata,
+            _ => return None,
+        };
+
+        // The file depends on Accept-Encoding, even for the clients that do not get the sibling
+        add_vary(http_response, "Accept-Encoding");
+
+        let accepted = http_request
+            .get_header("Accept-Encoding")
+            .is_some_and(|accept_encoding| ContentCoding::Gzip.is_accepted(accept_encoding));
+
+        if !accepted {
+            return None;
+        }
+
+        Some((compressed, metadata))
+    }
+
+    /// Returns the ranges of the file the client asked for, None means the whole file
+    /// If-Range sends the whole file when the client has an old version of it
+    fn get_ranges(
+        http_request: &HttpRequest,
+        etag: &str,
+        modified: Option<SystemTime>,
+        length: u64,
+    ) -> Option<Vec<ByteRange>> {
+        let range = http_request.get_header("Range")?;
+
+        if let Some(if_range) = http_request.get_header("If-Range") {
+            // Only strong validators can be used to join parts of a file
+            let current = if if_range.starts_with('"') {
+                if_range == etag
+            } else {
+                httpdate::parse_http_date(if_range).is_ok_and(|date| Some(date) == modified)
+            };
+
+            if !current {
+                return None;
+            }
+        }
+
+        parse_ranges(range, length)
+    }
+
+    /// Serves several ranges of a file as a multipart/byteranges body
+    /// The parts are read from the file while they are sent
+    fn serve_ranges(
+        http_response: &mut HttpResponse,
+        file: &Path,
+        content_type: &str,
+        etag: &str,
+        ranges: &[ByteRange],
+        length: u64,
+    ) -> Result<(), HttpError> {
+        let boundary = format!("byteranges-{}", etag.trim_matches('"'));
+
+        let mut body: Box<dyn Read + Send> = Box::new(std::io::empty());
+
+        let mut body_length = 0;
+
+        for range in ranges {
+            let head = format!(
+                "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+                boundary,
+                content_type,
+                range.content_range(length)
+            );
+
+            body_length += head.len() as u64 + range.get_length();
+
+            body = Box::new(
+                body.chain(Cursor::new(head.into_bytes()))
+                    .chain(Self::open_range(file, *range)?),
+            );
+        }
+
+        let tail = format!("\r\n--{}--\r\n", boundary);
+
+        body_length += tail.len() as u64;
+
+        body = Box::new(body.chain(Cursor::new(tail.into_bytes())));
+
+        http_response.set_code(StatusCode::PARTIAL_CONTENT);
+        http_response.add_header(
+            "Content-Type",
+            &format!("multipart/byteranges; boundary={}", boundary),
+        );
+        http_response.set_body_reader(body, Some(body_length));
+
+        Ok(())
+    }
+
+    /// Opens a file to read only the bytes of a range
+    fn open_range(file: &Path, range: ByteRange) -> Result<Box<dyn Read + Send>, HttpError> {
+        let mut data = fs::File::open(file)?;
+
+        data.seek(SeekFrom::Start(range.get_start()))?;
+
+        Ok(Box::new(data.take(range.get_length())))
+    }
+
+    /// Returns the Cache-Control of the longest prefix that matches a path
+    fn get_cache_control(&self, path: &str) -> Option<&str> {
+        self.cache_controls
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, cache_control)| cache_control.as_str())
+    }
+
+    /// Returns the modification time of a file, in whole seconds like the http dates
+    fn get_modified(metadata: &Metadata) -> Option<SystemTime> {
+        let modified = metadata.modified().ok()?;
+
+        let seconds = modified.duration_since(UNIX_EPOCH).ok()?.as_secs();
+
+        Some(UNIX_EPOCH + Duration::from_secs(seconds))
+    }
+
+    /// Returns an entity tag made of the modification time and the size of a file
+    fn get_etag(metadata: &Metadata, modified: Option<SystemTime>) -> String {
+        let seconds = modified
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_secs());
+
+        format!("\"{:x}-{:x}\"", seconds, metadata.len())
+    }
+
+    /// Returns true if the client already has the current version of the file
+    /// If-None-Match takes the place of If-Modified-Since when both are sent
+    fn is_not_modified(
+        http_request: &HttpRequest,
+        etag: &str,
+        modified: Option<SystemTime>,
+    ) -> bool {
+        if let Some(if_none_match) = http_request.get_header("If-None-Match") {
+            return Self::matches_etag(if_none_match, etag);
+        }
+
+        let since = http_request.get_headers().get_date("If-Modified-Since");
+
+        match (since, modified) {
+            (Some(since), Some(modified)) => modified <= since,
+            _ => false,
+        }
+    }
+
+    /// Returns true if a list of entity tags, or *, has one equal to etag
+    /// The comparison is weak, W/ is ignored
+    fn matches_etag(list: &str, etag: &str) -> bool {
+        list.split(',').map(str::trim).any(|candidate| {
+            candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+        })
+    }
+
+    /// Returns the methods allowed for a path, GET and HEAD if it has a file or a directory
+    pub fn get_allowed_methods(&self, path: &str) -> Vec<HttpMethod> {
+        match self.resolve(path) {
+            Some(_) => vec![HttpMethod::Get, HttpMethod::Head],
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the existing file of a request path, or None if the path can not be served
+    /// The path is decoded and normalized, it can not go outside the root or into hidden files
+    pub fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let segments = Self::get_segments(path)?;
+
+        let mut file = self.root.clone();
+
+        file.extend(&segments);
+
+        if !self.is_inside_root(&file) {
+            return None;
+        }
+
+        Some(file)
+    }
+
+    /// Returns the decoded segments of a request path, without empty ones, . and ..
+    /// Returns None if the path goes above the root or into hidden files
+    fn get_segments(path: &str) -> Option<Vec<String>> {
+        let mut segments: Vec<String> = Vec::new();
+
+        for segment in path.split('/') {
+            let segment = percent_decode(segment);
+
+            // An encoded separator or a null byte would change the meaning of the path
+            if segment.contains(['/', '\\', '\0']) {
+                return None;
+            }
+
+            match segment.as_str() {
+                "" | "." => {}
+                ".." => {
+                    segments.pop()?;
+                }
+                _ if segment.starts_with('.') => return None,
+                _ => segments.push(segment),
+            }
+        }
+
+        Some(segments)
+    }
+
+    /// Returns true if an existing file is inside the root
+    /// A symbolic link inside the root must not lead outside of it
+    fn is_inside_root(&self, file: &Path) -> bool {
+        match (fs::canonicalize(&self.root), fs::canonicalize(file)) {
+            (Ok(root), Ok(canonical)) => canonical.starts_with(root),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    /// A public root with some files, next to a file that must not be served
+    struct Fixture {
+        /// The directory with the root and the outside file
+        directory: PathBuf,
+        /// The files of the root
+        public: HttpStatic,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let directory =
+                env::temp_dir().join(format!("rust_server_static_{}_{}", process::id(), name));
+
+            let root = directory.join("public");
+
+            fs::create_dir_all(root.join("a/.git")).unwrap();
+            fs::write(root.join("a/b.txt"), "b").unwrap();
+            fs::write(root.join("a/.git/config"), "config").unwrap();
+            fs::write(root.join(".env"), "env").unwrap();
+            fs::write(directory.join("secret.txt"), "secret").unwrap();
+
+            let mut config = HttpConfig::new();
+
+            config.set_public_root(root.to_str().unwrap());
+
+            Self {
+                directory,
+                public: HttpStatic::new(&config),
+            }
+        }
+
+        /// Returns the path of a file of the root
+        fn file(&self, path: &str) -> PathBuf {
+            self.public.get_root().join(path)
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.directory);
+        }
+    }
+
+    #[test]
+    fn files_of_the_root_are_resolved() {
+        let fixture = Fixture::new("files");
+
+        assert_eq!(
+            fixture.public.resolve("/a/b.txt"),
+            Some(fixture.file("a/b.txt"))
+        );
+        assert_eq!(fixture.public.resolve("/a"), Some(fixture.file("a")));
+        assert_eq!(fixture.public.resolve("/missing.txt"), None);
+    }
+
+    #[test]
+    fn dots_are_collapsed_inside_the_root() {
+        let fixture = Fixture::new("dots");
+
+        for path in ["/a/./b.txt", "/a/../a/b.txt", "//a///b.txt", "/a/%2e/b.txt"] {
+            assert_eq!(
+                fixture.public.resolve(path),
+                Some(fixture.file("a/b.txt")),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn paths_can_not_go_above_the_root() {
+        let fixture = Fixture::new("above");
+
+        for path in [
+            "/../secret.txt",
+            "/%2e%2e/secret.txt",
+            "/%2E%2E/secret.txt",
+            "/a/../../secret.txt",
+            "/..",
+        ] {
+            assert_eq!(fixture.public.resolve(path), None, "{}", path);
+        }
+    }
+
+    #[test]
+    fn encoded_separators_and_null_bytes_are_rejected() {
+        let fixture = Fixture::new("encoded");
+
+        for path in [
+            "/a/%2F..",
+            "/a%2Fb.txt",
+            "/a%5Cb.txt",
+            "/a/b.txt%00",
+            "/a/%00/b.txt",
+        ] {
+            assert_eq!(fixture.public.resolve(path), None, "{}", path);
+        }
+    }
+
+    #[test]
+    fn hidden_segments_are_rejected() {
+        let fixture = Fixture::new("hidden");
+
+        for path in ["/.env", "/a/.git/config", "/a/.git", "/%2eenv"] {
+            assert_eq!(fixture.public.resolve(path), None, "{}", path);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_can_not_lead_outside_the_root() {
+        let fixture = Fixture::new("links");
+
+        std::os::unix::fs::symlink(&fixture.directory, fixture.file("outside")).unwrap();
+        std::os::unix::fs::symlink(fixture.file("a"), fixture.file("inside")).unwrap();
+
+        assert_eq!(fixture.public.resolve("/outside/secret.txt"), None);
+        assert_eq!(fixture.public.resolve("/outside"), None);
+        assert_eq!(
+            fixture.public.resolve("/inside/b.txt"),
+            Some(fixture.file("inside/b.txt"))
+        );
+    }
+}
@@ -0,0 +1,416 @@
+// Copyright 2023 Camilo Suárez Sandí
+
+use std::fmt;
+
+/// The status code of an http response, from 100 to 599
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct StatusCode(u16);
+
+/// The codes of the IANA registry
+impl StatusCode {
+    /// 100 Continue
+    pub const CONTINUE: StatusCode = StatusCode(100);
+
+    /// 101 Switching Protocols
+    pub const SWITCHING_PROTOCOLS: StatusCode = StatusCode(101);
+
+    /// 102 Processing
+    pub const PROCESSING: StatusCode = StatusCode(102);
+
+    /// 103 Early Hints
+    pub const EARLY_HINTS: StatusCode = StatusCode(103);
+
+    /// 200 OK
+    pub const OK: StatusCode = StatusCode(200);
+
+    /// 201 Created
+    pub const CREATED: StatusCode = StatusCode(201);
+
+    /// 202 Accepted
+    pub const ACCEPTED: StatusCode = StatusCode(202);
+
+    /// 203 Non-Authoritative Information
+    pub const NON_AUTHORITATIVE_INFORMATION: StatusCode = StatusCode(203);
+
+    /// 204 No Content
+    pub const NO_CONTENT: StatusCode = StatusCode(204);
+
+    /// 205 Reset Content
+    pub const RESET_CONTENT: StatusCode = StatusCode(205);
+
+    /// 206 Partial Content
+    pub const PARTIAL_CONTENT: StatusCode = StatusCode(206);
+
+    /// 207 Multi-Status
+    pub const MULTI_STATUS: StatusCode = StatusCode(207);
+
+    /// 208 Already Reported
+    pub const ALREADY_REPORTED: StatusCode = StatusCode(208);
+
+    /// 226 IM Used
+    pub const IM_USED: StatusCode = StatusCode(226);
+
+    /// 300 Multiple Choices
+    pub const MULTIPLE_CHOICES: StatusCode = StatusCode(300);
+
+    /// 301 Moved Permanently
+    pub const MOVED_PERMANENTLY: StatusCode = StatusCode(301);
+
+    /// 302 Found
+    pub const FOUND: StatusCode = StatusCode(302);
+
+    /// 303 See Other
+    pub const SEE_OTHER: StatusCode = StatusCode(303);
+
+    /// 304 Not Modified
+    pub const NOT_MODIFIED: StatusCode = StatusCode(304);
+
+    /// 305 Use Proxy
+    pub const USE_PROXY: StatusCode = StatusCode(305);
+
+    /// 307 Temporary Redirect
+    pub const TEMPORARY_REDIRECT: StatusCode = StatusCode(307);
+
+    /// 308 Permanent Redirect
+    pub const PERMANENT_REDIRECT: StatusCode = StatusCode(308);
+
+    /// 400 Bad Request
+    pub const BAD_REQUEST: StatusCode = StatusCode(400);
+
+    /// 401 Unauthorized
+    pub const UNAUTHORIZED: StatusCode = StatusCode(401);
+
+    /// 402 Payment Required
+    pub const PAYMENT_REQUIRED: StatusCode = StatusCode(402);
+
+    /// 403 Forbidden
+    pub const FORBIDDEN: StatusCode = StatusCode(403);
+
+    /// 404 Not Found
+    pub const NOT_FOUND: StatusCode = StatusCode(404);
+
+    /// 405 Method Not Allowed
+    pub const METHOD_NOT_ALLOWED: StatusCode = StatusCode(405);
+
+    /// 406 Not Acceptable
+    pub const NOT_ACCEPTABLE: StatusCode = StatusCode(406);
+
+    /// 407 Proxy Authentication Required
+    pub const PROXY_AUTHENTICATION_REQUIRED: StatusCode = StatusCode(407);
+
+    /// 408 Request Timeout
+    pub const REQUEST_TIMEOUT: StatusCode = StatusCode(408);
+
+    /// 409 Conflict
+    pub const CONFLICT: StatusCode = StatusCode(409);
+
+    /// 410 Gone
+    pub const GONE: StatusCode = StatusCode(410);
+
+    /// 411 Length Required
+    pub const LENGTH_REQUIRED: StatusCode = StatusCode(411);
+
+    /// 412 Precondition Failed
+    pub const PRECONDITION_FAILED: StatusCode = StatusCode(412);
+
+    /// 413 Content Too Large
+    pub const CONTENT_TOO_LARGE: StatusCode = StatusCode(413);
+
+    /// 414 URI Too Long
+    pub const URI_TOO_LONG: StatusCode = StatusCode(414);
+
+    /// 415 Unsupported Media Type
+    pub const UNSUPPORTED_MEDIA_TYPE: StatusCode = StatusCode(415);
+
+    /// 416 Range Not Satisfiable
+    pub const RANGE_NOT_SATISFIABLE: StatusCode = StatusCode(416);
+
+    /// 417 Expectation Failed
+    pub const EXPECTATION_FAILED: StatusCode = StatusCode(417);
+
+    /// 421 Misdirected Request
+    pub const MISDIRECTED_REQUEST: StatusCode = StatusCode(421);
+
+    /// 422 Unprocessable Content
+    pub const UNPROCESSABLE_CONTENT: StatusCode = StatusCode(422);
+
+    /// 423 Locked
+    pub const LOCKED: StatusCode = StatusCode(423);
+
+    /// 424 Failed Dependency
+    pub const FAILED_DEPENDENCY: StatusCode = StatusCode(424);
+
+    /// 425 Too Early
+    pub const TOO_EARLY: StatusCode = StatusCode(425);
+
+    /// 426 Upgrade Required
+    pub const UPGRADE_REQUIRED: StatusCode = StatusCode(426);
+
+    /// 428 Precondition Required
+    pub const PRECONDITION_REQUIRED: StatusCode = StatusCode(428);
+
+    /// 429 Too Many Requests
+    pub const TOO_MANY_REQUESTS: StatusCode = StatusCode(429);
+
+    /// 431 Request Header Fields Too Large
+    pub const REQUEST_HEADER_FIELDS_TOO_LARGE: StatusCode = StatusCode(431);
+
+    /// 451 Unavailable For Legal Reasons
+    pub const UNAVAILABLE_FOR_LEGAL_REASONS: StatusCode = StatusCode(451);
+
+    /// 500 Internal Server Error
+    pub const INTERNAL_SERVER_ERROR: StatusCode = StatusCode(500);
+
+    /// 501 Not Implemented
+    pub const NOT_IMPLEMENTED: StatusCode = StatusCode(501);
+
+    /// 502 Bad Gateway
+    pub const BAD_GATEWAY: StatusCode = StatusCode(502);
+
+    /// 503 Service Unavailable
+    pub const SERVICE_UNAVAILABLE: StatusCode = StatusCode(503);
+
+    /// 504 Gateway Timeout
+    pub const GATEWAY_TIMEOUT: StatusCode = StatusCode(504);
+
+    /// 505 HTTP Version Not Supported
+    pub const HTTP_VERSION_NOT_SUPPORTED: StatusCode = StatusCode(505);
+
+    /// 506 Variant Also Negotiates
+    pub const VARIANT_ALSO_NEGOTIATES: StatusCode = StatusCode(506);
+
+    /// 507 Insufficient Storage
+    pub const INSUFFICIENT_STORAGE: StatusCode = StatusCode(507);
+
+    /// 508 Loop Detected
+    pub const LOOP_DETECTED: StatusCode = StatusCode(508);
+
+    /// 510 Not Extended
+    pub const NOT_EXTENDED: StatusCode = StatusCode(510);
+
+    /// 511 Network Authentication Required
+    pub const NETWORK_AUTHENTICATION_REQUIRED: StatusCode = StatusCode(511);
+}
+
+impl StatusCode {
+    /// StatusCode constructor
+    /// Returns a status code, or None if it is not between 100 and 599
+    pub fn new(code: u16) -> Option<Self> {
+        if (100..600).contains(&code) {
+            Some(Self(code))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of the code, like 404
+    pub fn get_code(&self) -> u16 {
+        self.0
+    }
+
+    /// Returns the reason phrase of the code, like Not Found for 404
+    /// A code that is not registered gets the name of its class
+    pub fn get_reason_phrase(&self) -> &'static str {
+        match self.0 {
+            100 => "Continue",
+            101 => "Switching Protocols",
+            102 => "Processing",
+            103 => "Early Hints",
+            200 => "OK",
+            201 => "Created",
+            202 => "Accepted",
+            203 => "Non-Authoritative Information",
+            204 => "No Content",
+            205 => "Reset Content",
+            206 => "Partial Content",
+            207 => "Multi-Status",
+            208 => "Already Reported",
+            226 => "IM Used",
+            300 => "Multiple Choices",
+            301 => "Moved Permanently",
+            302 => "Found",
+            303 => "See Other",
+            304 => "Not Modified",
+            305 => "Use Proxy",
+            307 => "Temporary Redirect",
+            308 => "Permanent Redirect",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            402 => "Payment Required",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            406 => "Not Acceptable",
+            407 => "Proxy Authentication Required",
+            408 => "Request Timeout",
+            409 => "Conflict",
+            410 => "Gone",
+            411 => "Length Required",
+            412 => "Precondition Failed",
+            413 => "Content Too Large",
+            414 => "URI Too Long",
+            415 => "Unsupported Media Type",
+            416 => "Range Not Satisfiable",
+            417 => "Expectation Failed",
+            421 => "Misdirected Request",
+            422 => "Unprocessable Content",
+            423 => "Locked",
+            424 => "Failed Dependency",
+            425 => "Too Early",
+            426 => "Upgrade Required",
+            428 => "Precondition Required",
+            429 => "Too Many Requests",
+            431 => "Request Header Fields Too Large",
+            451 => "Unavailable For Legal Reasons",
+            500 => "Internal Server Error",
+            501 => "Not Implemented",
+            502 => "Bad Gateway",
+            503 => "Service Unavailable",
+            504 => "Gateway Timeout",
+            505 => "HTTP Version Not Supported",
+            506 => "Variant Also Negotiates",
+            507 => "Insufficient Storage",
+            508 => "Loop Detected",
+            510 => "Not Extended",
+            511 => "Network Authentication Required",
+            _ => self.get_class_name(),
+        }
+    }
+
+    /// Returns the name of the class of the code, like Client Error for 4xx
+    fn get_class_name(&self) -> &'static str {
+        match self.0 / 100 {
+            1 => "Informational",
+            2 => "Success",
+            3 => "Redirection",
+            4 => "Client Error",
+            _ => "Server Error",
+        }
+    }
+
+    /// Returns true for 1xx codes
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.0)
+    }
+
+    /// Returns true for 2xx codes
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.0)
+    }
+
+    /// Returns true for 3xx codes
+    pub fn is_redirect(&self) -> bool {
+        (300..400).contains(&self.0)
+    }
+
+    /// Returns true for 4xx codes
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.0)
+    }
+
+    /// Returns true for 5xx codes
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.0)
+    }
+
+    /// Returns true for 4xx and 5xx codes
+    pub fn is_error(&self) -> bool {
+        self.is_client_error() || self.is_server_error()
+    }
+
+    /// Returns true if a response with this code can have a body, 1xx, 204 and 304 never have one
+    pub fn allows_body(&self) -> bool {
+        !(self.is_informational()
+            || *self == StatusCode::NO_CONTENT
+            || *self == StatusCode::NOT_MODIFIED)
+    }
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.0, self.get_reason_phrase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_accepts_codes_from_100_to_599() {
+        assert_eq!(StatusCode::new(100), Some(StatusCode::CONTINUE));
+        assert_eq!(StatusCode::new(404), Some(StatusCode::NOT_FOUND));
+        assert_eq!(StatusCode::new(599).map(|code| code.get_code()), Some(599));
+    }
+
+    #[test]
+    fn new_rejects_codes_outside_the_range() {
+        for code in [0, 99, 600, 999, 9999] {
+            assert_eq!(StatusCode::new(code), None, "{}", code);
+        }
+    }
+
+    #[test]
+    fn registered_codes_have_their_reason_phrase() {
+        assert_eq!(StatusCode::OK.get_reason_phrase(), "OK");
+        assert_eq!(StatusCode::FOUND.get_reason_phrase(), "Found");
+        assert_eq!(
+            StatusCode::CONTENT_TOO_LARGE.get_reason_phrase(),
+            "Content Too Large"
+        );
+        assert_eq!(
+            StatusCode::NETWORK_AUTHENTICATION_REQUIRED.get_reason_phrase(),
+            "Network Authentication Required"
+        );
+    }
+
+    #[test]
+    fn unregistered_codes_get_the_name_of_their_class() {
+        let phrase = |code| StatusCode::new(code).unwrap().get_reason_phrase();
+
+        assert_eq!(phrase(199), "Informational");
+        assert_eq!(phrase(299), "Success");
+        assert_eq!(phrase(306), "Redirection");
+        assert_eq!(phrase(499), "Client Error");
+        assert_eq!(phrase(599), "Server Error");
+    }
+
+    #[test]
+    fn class_helpers() {
+        assert!(StatusCode::CONTINUE.is_informational());
+        assert!(StatusCode::NO_CONTENT.is_success());
+        assert!(StatusCode::SEE_OTHER.is_redirect());
+        assert!(StatusCode::NOT_FOUND.is_client_error());
+        assert!(StatusCode::BAD_GATEWAY.is_server_error());
+
+        assert!(!StatusCode::OK.is_redirect());
+        assert!(!StatusCode::PERMANENT_REDIRECT.is_client_error());
+        assert!(!StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS.is_server_error());
+        assert!(!StatusCode::INTERNAL_SERVER_ERROR.is_client_error());
+    }
+
+    #[test]
+    fn is_error_covers_client_and_server_errors() {
+        assert!(StatusCode::BAD_REQUEST.is_error());
+        assert!(StatusCode::SERVICE_UNAVAILABLE.is_error());
+        assert!(!StatusCode::NOT_MODIFIED.is_error());
+        assert!(!StatusCode::OK.is_error());
+    }
+
+    #[test]
+    fn allows_body() {
+        assert!(!StatusCode::CONTINUE.allows_body());
+        assert!(!StatusCode::EARLY_HINTS.allows_body());
+        assert!(!StatusCode::NO_CONTENT.allows_body());
+        assert!(!StatusCode::NOT_MODIFIED.allows_body());
+
+        assert!(StatusCode::OK.allows_body());
+        assert!(StatusCode::PARTIAL_CONTENT.allows_body());
+        assert!(StatusCode::NOT_FOUND.allows_body());
+    }
+
+    #[test]
+    fn display_has_the_code_and_the_reason_phrase() {
+        assert_eq!(StatusCode::NOT_FOUND.to_string(), "404 Not Found");
+    }
+}
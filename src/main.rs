@@ -25,8 +25,12 @@ fn main() -> Result<(), Error> {
 
     let tasks_app = Box::new(TasksApp::new());
 
+    tasks_app.read_data()?;
+
     server.add_app(tasks_app);
 
+    server.shutdown_on_signals()?;
+
     server.start()?;
 
     Ok(())
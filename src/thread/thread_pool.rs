@@ -1,50 +1,199 @@
 // Copyright 2023 Camilo Suárez Sandí
 
 use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
     sync::{
-        mpsc::{self, Receiver, Sender},
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
         Arc, Mutex,
     },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
-type ThreadWork = Option<Box<dyn FnOnce() + Send + 'static>>;
+/// How often a shutdown checks whether the workers finished
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Returns the message of a panic, if it has one
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+
+    if let Some(message) = payload.downcast_ref::<String>() {
+        return message.clone();
+    }
+
+    "unknown panic".to_string()
+}
+
+type ThreadWork = Box<dyn FnOnce() + Send + 'static>;
 
 pub struct ThreadPool {
     workers: Vec<ThreadWorker>,
-    sender: Sender<ThreadWork>,
+    /// Sends work to a queue of limited capacity
+    /// It is dropped to stop the workers, once they finish the queued work
+    sender: Option<SyncSender<ThreadWork>>,
+    /// Kept to start new workers in place of the ones that died
+    receiver: Arc<Mutex<Receiver<ThreadWork>>>,
+    /// The number of jobs that panicked
+    panics: Arc<AtomicUsize>,
+    /// The number of jobs waiting in the queue
+    queue_depth: Arc<AtomicUsize>,
+    /// The maximum number of jobs waiting in the queue
+    queue_capacity: usize,
 }
 
 impl ThreadPool {
-    pub fn new(workers_count: usize) -> ThreadPool {
-        let (sender, receiver) = mpsc::channel();
+    /// ThreadPool constructor
+    /// Returns a pool of workers whose queue holds up to queue_capacity jobs
+    pub fn new(workers_count: usize, queue_capacity: usize) -> ThreadPool {
+        Self::with_queue_depth(workers_count, queue_capacity, Arc::new(AtomicUsize::new(0)))
+    }
+
+    /// ThreadPool constructor
+    /// Like new, but the depth of the queue is kept in a counter shared with the caller
+    pub fn with_queue_depth(
+        workers_count: usize,
+        queue_capacity: usize,
+        queue_depth: Arc<AtomicUsize>,
+    ) -> ThreadPool {
+        let (sender, receiver) = mpsc::sync_channel(queue_capacity);
 
         let receiver = Arc::new(Mutex::new(receiver));
 
+        let panics = Arc::new(AtomicUsize::new(0));
+
         let mut workers = Vec::with_capacity(workers_count);
 
-        for _ in 0..workers_count {
-            workers.push(ThreadWorker::new(Arc::clone(&receiver)));
+        for id in 0..workers_count {
+            workers.push(ThreadWorker::new(
+                id,
+                Arc::clone(&receiver),
+                Arc::clone(&panics),
+                Arc::clone(&queue_depth),
+            ));
         }
 
-        Self { workers, sender }
+        Self {
+            workers,
+            sender: Some(sender),
+            receiver,
+            panics,
+            queue_depth,
+            queue_capacity,
+        }
+    }
+
+    /// Queues work for the workers, waiting while the queue is full
+    pub fn execute<F>(&mut self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.respawn_workers();
+
+        let work = Box::new(f);
+
+        self.queue_depth.fetch_add(1, Ordering::SeqCst);
+
+        self.get_sender().send(work).unwrap();
     }
 
-    pub fn execute<F>(&self, f: F)
+    /// Queues work for the workers if the queue has room
+    /// Returns false, dropping the work, if the queue is full
+    pub fn try_execute<F>(&mut self, f: F) -> bool
     where
         F: FnOnce() + Send + 'static,
     {
+        self.respawn_workers();
+
         let work = Box::new(f);
 
-        self.sender.send(Some(work)).unwrap();
+        self.queue_depth.fetch_add(1, Ordering::SeqCst);
+
+        match self.get_sender().try_send(work) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.queue_depth.fetch_sub(1, Ordering::SeqCst);
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => panic!("The workers of the pool stopped"),
+        }
+    }
+
+    /// Returns the sender of the queue, which is only gone while the pool is dropped
+    fn get_sender(&self) -> &SyncSender<ThreadWork> {
+        self.sender.as_ref().expect("The pool was shut down")
+    }
+
+    /// Returns the number of jobs waiting for a worker
+    pub fn get_queue_depth(&self) -> usize {
+        self.queue_depth.load(Ordering::SeqCst)
+    }
+
+    /// Getter for the queue capacity
+    pub fn get_queue_capacity(&self) -> usize {
+        self.queue_capacity
+    }
+
+    /// Returns the number of jobs that panicked since the pool started
+    pub fn get_panic_count(&self) -> usize {
+        self.panics.load(Ordering::SeqCst)
+    }
+
+    /// Starts a new worker in place of each one whose thread ended
+    /// Panics in jobs are caught, so this only happens if a worker itself failed
+    fn respawn_workers(&mut self) {
+        for (id, worker) in self.workers.iter_mut().enumerate() {
+            if worker.is_finished() {
+                eprintln!("Worker {} stopped, starting a new one", id);
+
+                *worker = ThreadWorker::new(
+                    id,
+                    Arc::clone(&self.receiver),
+                    Arc::clone(&self.panics),
+                    Arc::clone(&self.queue_depth),
+                );
+            }
+        }
+    }
+
+    /// Lets the workers finish the queued and running work, waiting up to a timeout
+    /// Returns false if some workers were still busy when the timeout expired
+    pub fn shutdown(mut self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+
+        // Dropping the sender never waits, even with a full queue
+        drop(self.sender.take());
+
+        let mut workers = std::mem::take(&mut self.workers);
+
+        while Instant::now() < deadline && workers.iter().any(|worker| !worker.is_finished()) {
+            thread::sleep(SHUTDOWN_POLL_INTERVAL);
+        }
+
+        let mut finished = true;
+
+        for worker in &mut workers {
+            if !worker.is_finished() {
+                // The worker is left running, it ends with the process
+                finished = false;
+                continue;
+            }
+
+            if let Some(thread) = worker.thread.take() {
+                thread.join().unwrap();
+            }
+        }
+
+        finished
     }
 }
 
 impl Drop for ThreadPool {
     fn drop(&mut self) {
-        for _ in 0..self.workers.len() {
-            self.sender.send(None).unwrap();
-        }
+        drop(self.sender.take());
 
         for worker in &mut self.workers {
             if let Some(thread) = worker.thread.take() {
@@ -59,15 +208,32 @@ struct ThreadWorker {
 }
 
 impl ThreadWorker {
-    fn new(receiver: Arc<Mutex<Receiver<ThreadWork>>>) -> Self {
+    fn new(
+        id: usize,
+        receiver: Arc<Mutex<Receiver<ThreadWork>>>,
+        panics: Arc<AtomicUsize>,
+        queue_depth: Arc<AtomicUsize>,
+    ) -> Self {
         let thread = thread::spawn(move || loop {
-            let work = receiver.lock().unwrap().recv().unwrap();
+            // The queue is disconnected once the pool drops its sender and the work runs out
+            let work = receiver.lock().unwrap().recv();
 
             match work {
-                Some(message) => {
-                    message();
+                Ok(message) => {
+                    queue_depth.fetch_sub(1, Ordering::SeqCst);
+
+                    // A panic ends the job, but not the worker
+                    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(message)) {
+                        panics.fetch_add(1, Ordering::SeqCst);
+
+                        eprintln!(
+                            "Worker {}: a job panicked: {}",
+                            id,
+                            panic_message(payload.as_ref())
+                        );
+                    }
                 }
-                None => {
+                Err(_) => {
                     break;
                 }
             }
@@ -77,4 +243,64 @@ impl ThreadWorker {
             thread: Some(thread),
         }
     }
+
+    fn is_finished(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_none_or(|thread| thread.is_finished())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shutdown_runs_the_queued_work() {
+        let mut pool = ThreadPool::new(2, 4);
+
+        let done = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..4 {
+            let done = Arc::clone(&done);
+
+            pool.execute(move || {
+                done.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        assert!(pool.shutdown(Duration::from_secs(5)));
+        assert_eq!(done.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn shutdown_with_a_full_queue_respects_the_timeout() {
+        let mut pool = ThreadPool::new(1, 1);
+
+        // One job keeps the worker busy and the other one fills the queue
+        pool.execute(|| thread::sleep(Duration::from_secs(2)));
+        pool.execute(|| thread::sleep(Duration::from_secs(2)));
+
+        let start = Instant::now();
+
+        assert!(!pool.shutdown(Duration::from_millis(100)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn panics_do_not_stop_the_workers() {
+        let mut pool = ThreadPool::new(1, 2);
+
+        let done = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&done);
+
+        pool.execute(|| panic!("job failed"));
+        pool.execute(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        assert!(pool.shutdown(Duration::from_secs(5)));
+        assert_eq!(done.load(Ordering::SeqCst), 1);
+    }
 }